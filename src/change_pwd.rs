@@ -0,0 +1,273 @@
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use crate::util::common::{self, ValidStatus};
+use crate::util::config;
+use crate::util::error::{ErrorKind, ToError};
+use crate::util::request;
+use crate::util::session;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use yew::prelude::*;
+
+#[derive(Serialize, Clone, Default)]
+pub struct UpdatePwdReq {
+    old_pwd: String,
+    pwd: String,
+}
+
+pub struct ChangePwd {
+    req: UpdatePwdReq,
+    pwd_confirm: String,
+    old_pwd_valid: ValidStatus,
+    pwd_valid: ValidStatus,
+    pwd_confirm_valid: ValidStatus,
+    request_fail_msg: String,
+    loading: bool,
+    messages: Arc<Mutex<MessageListValue>>,
+}
+
+pub enum ChangePwdMsg {
+    OldPasswordChange(web_sys::KeyboardEvent),
+    PasswordChange(web_sys::KeyboardEvent),
+    PasswordConfirmChange(web_sys::KeyboardEvent),
+    KeyDownSubmit(web_sys::KeyboardEvent),
+    Submit,
+    HandleChangePwdSuccess(Option<String>),
+    HandleChangePwdError(Box<dyn std::error::Error>),
+    HandleRefreshSuccess,
+    HandleRefreshError(Box<dyn std::error::Error>),
+}
+
+fn input_class(status: &ValidStatus) -> &'static str {
+    match status {
+        ValidStatus::Valid => "input is-success",
+        ValidStatus::InValid(_) => "input is-danger",
+        ValidStatus::None => "input is-info",
+    }
+}
+
+fn invalid_msg(status: &ValidStatus) -> String {
+    match status {
+        ValidStatus::InValid(e) => e.clone(),
+        _ => String::new(),
+    }
+}
+
+impl ChangePwd {
+    fn validate(&mut self) -> bool {
+        self.old_pwd_valid = if self.req.old_pwd.is_empty() {
+            ValidStatus::InValid("please type in current password".to_string())
+        } else {
+            ValidStatus::Valid
+        };
+        self.pwd_valid = match common::validate_pwd(&self.req.pwd) {
+            Ok(_) if self.req.pwd == self.req.old_pwd => ValidStatus::InValid(
+                "new password must be different from the current one".to_string(),
+            ),
+            Ok(_) => ValidStatus::Valid,
+            Err(e) => ValidStatus::InValid(format!("{}", e)),
+        };
+        self.pwd_confirm_valid =
+            match common::validate_pwd_confirm(&self.req.pwd, &self.pwd_confirm) {
+                Ok(_) => ValidStatus::Valid,
+                Err(e) => ValidStatus::InValid(format!("{}", e)),
+            };
+        [
+            &self.old_pwd_valid,
+            &self.pwd_valid,
+            &self.pwd_confirm_valid,
+        ]
+        .iter()
+        .all(|x| **x == ValidStatus::Valid)
+    }
+}
+
+impl Component for ChangePwd {
+    type Message = ChangePwdMsg;
+
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            req: Default::default(),
+            pwd_confirm: Default::default(),
+            old_pwd_valid: Default::default(),
+            pwd_valid: Default::default(),
+            pwd_confirm_valid: Default::default(),
+            request_fail_msg: Default::default(),
+            loading: false,
+            messages: Default::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ChangePwdMsg::OldPasswordChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                self.req.old_pwd = el.value();
+                true
+            }
+            ChangePwdMsg::PasswordChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                self.req.pwd = el.value();
+                self.pwd_valid = match common::validate_pwd(&self.req.pwd) {
+                    Ok(_) => ValidStatus::Valid,
+                    Err(e) => ValidStatus::InValid(format!("{}", e)),
+                };
+                true
+            }
+            ChangePwdMsg::PasswordConfirmChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                self.pwd_confirm = el.value();
+                self.pwd_confirm_valid =
+                    match common::validate_pwd_confirm(&self.req.pwd, &self.pwd_confirm) {
+                        Ok(_) => ValidStatus::Valid,
+                        Err(e) => ValidStatus::InValid(format!("{}", e)),
+                    };
+                true
+            }
+            ChangePwdMsg::KeyDownSubmit(e) => {
+                if e.key_code() == 13 {
+                    ctx.link().send_message(ChangePwdMsg::Submit);
+                }
+                false
+            }
+            ChangePwdMsg::Submit => {
+                self.request_fail_msg.clear();
+                if self.loading || !self.validate() {
+                    return true;
+                }
+                self.loading = true;
+                let req = self.req.clone();
+                ctx.link().send_future(async move {
+                    match request::put::<String, _>(
+                        request::Host::ApiBase,
+                        "/user/update_pwd",
+                        &req,
+                    )
+                    .await
+                    {
+                        Ok(res) => ChangePwdMsg::HandleChangePwdSuccess(res.data),
+                        Err(err) => ChangePwdMsg::HandleChangePwdError(Box::new(err)),
+                    }
+                });
+                true
+            }
+            ChangePwdMsg::HandleChangePwdSuccess(token) => {
+                // the backend drops every session of the user, the response carries the new one
+                match token {
+                    Some(token) => {
+                        common::set_token(&token);
+                        ctx.link().send_future(async move {
+                            match common::refresh_current_user().await {
+                                Ok(_) => ChangePwdMsg::HandleRefreshSuccess,
+                                Err(err) => ChangePwdMsg::HandleRefreshError(Box::new(err)),
+                            }
+                        });
+                    }
+                    None => ctx
+                        .link()
+                        .send_message(ChangePwdMsg::HandleRefreshError(Box::new(
+                            "session invalidated".to_basic_error(),
+                        ))),
+                }
+                false
+            }
+            ChangePwdMsg::HandleChangePwdError(e) => {
+                self.loading = false;
+                // the backend may refuse a single input, show it next to that one
+                if let Some(err) = e.downcast_ref::<ErrorKind>() {
+                    for (name, valid) in [
+                        ("old_pwd", &mut self.old_pwd_valid),
+                        ("pwd", &mut self.pwd_valid),
+                    ] {
+                        if let Some(msg) = err.field(name) {
+                            *valid = ValidStatus::InValid(msg.to_string());
+                        }
+                    }
+                }
+                self.request_fail_msg = format!("{}", e);
+                true
+            }
+            ChangePwdMsg::HandleRefreshSuccess => {
+                self.loading = false;
+                self.req = Default::default();
+                self.pwd_confirm.clear();
+                self.old_pwd_valid = ValidStatus::None;
+                self.pwd_valid = ValidStatus::None;
+                self.pwd_confirm_valid = ValidStatus::None;
+                self.messages.ok("password changed");
+                true
+            }
+            ChangePwdMsg::HandleRefreshError(e) => {
+                // password is changed but the session is gone, log in again with the new one
+                log::warn!("refresh session error: {}", e);
+                session::logout();
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let button_class = if self.loading {
+            "button is-primary is-loading"
+        } else {
+            "button is-primary"
+        };
+        html! {
+            <>
+            <MessageList value = {self.messages.clone()} ws = {config::get().features.chat}/>
+            <div class="change-pwd-container">
+                <div class="box">
+                    <h4 class="title is-4">{"Change password"}</h4>
+                    <div class="field">
+                        <label class="label">{"Current Password:"}</label>
+                        <p class="control has-icons-left">
+                            <input class={input_class(&self.old_pwd_valid)} value={self.req.old_pwd.clone()} type="password" onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|ChangePwdMsg::OldPasswordChange(e))} placeholder="**********"/>
+                            <span class="icon is-small is-left">
+                            <i class="fas fa-lock"></i>
+                            </span>
+                        </p>
+                        <p class="help is-danger">
+                            {invalid_msg(&self.old_pwd_valid)}
+                        </p>
+                    </div>
+                    <div class="field">
+                        <label class="label">{"New Password:"}</label>
+                        <p class="control has-icons-left">
+                            <input class={input_class(&self.pwd_valid)} value={self.req.pwd.clone()} type="password" onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|ChangePwdMsg::PasswordChange(e))} placeholder="**********"/>
+                            <span class="icon is-small is-left">
+                            <i class="fas fa-lock"></i>
+                            </span>
+                        </p>
+                        <p class="help is-danger">
+                            {invalid_msg(&self.pwd_valid)}
+                        </p>
+                    </div>
+                    <div class="field">
+                        <label class="label">{"Re-enter New Password:"}</label>
+                        <p class="control has-icons-left">
+                            <input class={input_class(&self.pwd_confirm_valid)} value={self.pwd_confirm.clone()} type="password" onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|ChangePwdMsg::PasswordConfirmChange(e))} onkeydown={ctx.link().callback(|e:web_sys::KeyboardEvent|ChangePwdMsg::KeyDownSubmit(e))} placeholder="**********"/>
+                            <span class="icon is-small is-left">
+                            <i class="fas fa-lock"></i>
+                            </span>
+                        </p>
+                        <p class="help is-danger">
+                            {invalid_msg(&self.pwd_confirm_valid)}
+                        </p>
+                    </div>
+                    <div class="field">
+                        <p class="control">
+                            <button class={button_class} disabled={self.loading} onclick={ctx.link().callback(|_|ChangePwdMsg::Submit)}>
+                            {"Change Password"}
+                            </button>
+                        </p>
+                        <p class="help is-danger">
+                            {self.request_fail_msg.clone()}
+                        </p>
+                    </div>
+                </div>
+            </div>
+            </>
+        }
+    }
+}
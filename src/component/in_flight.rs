@@ -0,0 +1,65 @@
+use crate::util::request::{self, InFlight};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct InFlightProviderProps {
+    pub children: Children,
+}
+
+/// Provides the requests in flight as an `InFlight` context to everything below it.
+#[function_component(InFlightProvider)]
+pub fn in_flight_provider(props: &InFlightProviderProps) -> Html {
+    let in_flight = use_state(request::in_flight);
+    {
+        let in_flight = in_flight.clone();
+        use_effect_with_deps(
+            move |_| {
+                request::set_in_flight_listener(Some(Callback::from(move |v| in_flight.set(v))));
+                || request::set_in_flight_listener(None)
+            },
+            (),
+        );
+    }
+    html! {
+        <ContextProvider<InFlight> context={(*in_flight).clone()}>
+            { props.children.clone() }
+        </ContextProvider<InFlight>>
+    }
+}
+
+/// The `InFlight` context as a struct component holds it, kept current through one of its messages.
+pub struct InFlightState {
+    value: InFlight,
+    _handle: Option<ContextHandle<InFlight>>,
+}
+
+impl InFlightState {
+    /// `f` wraps each change into a message, handled with `update`
+    pub fn subscribe<C, F>(ctx: &Context<C>, f: F) -> Self
+    where
+        C: Component,
+        F: Fn(InFlight) -> C::Message + 'static,
+    {
+        match ctx.link().context::<InFlight>(ctx.link().callback(f)) {
+            Some((value, handle)) => Self {
+                value,
+                _handle: Some(handle),
+            },
+            None => Self {
+                value: Default::default(),
+                _handle: None,
+            },
+        }
+    }
+
+    pub fn is_pending(&self, key: &str) -> bool {
+        self.value.is_pending(key)
+    }
+
+    /// take the changed context, true when `key` went pending or settled
+    pub fn update(&mut self, value: InFlight, key: &str) -> bool {
+        let changed = self.value.is_pending(key) != value.is_pending(key);
+        self.value = value;
+        changed
+    }
+}
@@ -28,6 +28,9 @@ pub struct MenuLabel {
 pub struct MenuNode {
     pub name: String,
     pub children: Vec<MenuNode>,
+    // a count or the like, shown after the name
+    #[serde(default)]
+    pub tag: Option<String>,
 }
 
 impl MenuNode {
@@ -41,7 +44,15 @@ impl MenuNode {
         }
         html! {
             <li>
-                <a href={String::from("javascript:void(0)")} class={class} onclick = {ctx.link().callback(move|_|MenuMsg::OnSelect(name.clone()))}>{&self.name}</a>
+                <a href={String::from("javascript:void(0)")} class={class} onclick = {ctx.link().callback(move|_|MenuMsg::OnSelect(name.clone()))}>
+                    {&self.name}
+                    {
+                        match &self.tag {
+                            Some(tag) => html!{<span class="tag is-rounded menu-tag">{tag}</span>},
+                            None => html!{},
+                        }
+                    }
+                </a>
                 {
                     if self.children.is_empty() {
                         html!{}
@@ -1,14 +1,12 @@
 // use crate::component::message_item::MessageItemValue;
 use crate::component::menu::{Menu, MenuLabel, MenuNode};
-use crate::component::message_list::MessageContent;
-use futures::stream::SplitSink;
-use futures::SinkExt;
-use gloo_net::websocket::{futures::WebSocket, Message};
+use crate::component::message_list::{self, MessageContent};
+use crate::util::protocol::{ClientFrame, SendState};
+use crate::util::ws;
 use std::collections::HashMap;
 use std::collections::LinkedList;
 use std::sync::{Arc, Mutex};
 // use utilities::datetime::FormatDateTime;
-use wasm_bindgen_futures::spawn_local;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
 use yew::Properties;
@@ -16,13 +14,19 @@ use yew::Properties;
 pub struct MessageDialog {
     refs: Vec<NodeRef>,
     selected_room: Option<String>,
+    // created or joined, selected once the server's join frame for us puts it in `rooms`
+    joining_room: Option<String>,
+    room_error: Option<String>,
 }
 
 pub enum MessageDialogMsg {
     Close,
     Send(web_sys::KeyboardEvent),
     ClickRoom(String),
-    Notify,
+    ClickSession(String),
+    CreateRoom,
+    JoinRoom,
+    QuitRoom,
 }
 
 #[derive(Clone, Properties)]
@@ -39,11 +43,20 @@ pub struct MessageDialogProps {
     #[prop_or_default]
     pub messages: Arc<Mutex<HashMap<String, LinkedList<MessageContent>>>>,
 
+    /// direct conversation key -> messages not read yet
     #[prop_or_default]
-    pub ws_writer: Option<Arc<Mutex<SplitSink<WebSocket, Message>>>>,
+    pub unread: Arc<Mutex<HashMap<String, usize>>>,
 
     #[prop_or_default]
     pub latest_message: Option<MessageContent>,
+
+    /// (room, content)
+    #[prop_or_default]
+    pub on_send: Callback<(String, String)>,
+
+    /// client id of a failed message
+    #[prop_or_default]
+    pub on_retry: Callback<String>,
 }
 
 impl PartialEq for MessageDialogProps {
@@ -52,6 +65,125 @@ impl PartialEq for MessageDialogProps {
     }
 }
 
+impl MessageDialog {
+    /// the trimmed room name input, cleared
+    fn take_room_name(&mut self) -> Option<String> {
+        let input = self.refs[2].cast::<HtmlInputElement>()?;
+        let room = input.value().trim().to_string();
+        if room.is_empty() {
+            self.room_error = Some("please type in a room name".to_string());
+            return None;
+        }
+        // such a name would read as a direct conversation
+        if message_list::direct_peer(&room).is_some() {
+            self.room_error = Some("a room name can not start with @".to_string());
+            return None;
+        }
+        self.room_error = None;
+        input.set_value("");
+        Some(room)
+    }
+
+    /// the name a session goes by in any room, or in what it sent us
+    fn peer_name(ctx: &Context<Self>, session_id: &str) -> String {
+        let in_room = ctx
+            .props()
+            .rooms
+            .lock()
+            .unwrap()
+            .values()
+            .find_map(|x| x.get(session_id).cloned());
+        if let Some(name) = in_room {
+            return name;
+        }
+        ctx.props()
+            .messages
+            .lock()
+            .unwrap()
+            .get(&message_list::direct_key(session_id))
+            .and_then(|x| x.iter().find(|m| m.is_own.is_none()))
+            .map(|m| m.from_name.clone())
+            .unwrap_or(session_id.to_string())
+    }
+
+    fn view_directs(&self, ctx: &Context<Self>) -> Html {
+        let unread = ctx.props().unread.lock().unwrap().clone();
+        // collected first, peer_name locks the messages again
+        let peers = ctx
+            .props()
+            .messages
+            .lock()
+            .unwrap()
+            .keys()
+            .filter_map(|x| message_list::direct_peer(x).map(|v| v.to_string()))
+            .collect::<Vec<_>>();
+        let mut directs = peers
+            .into_iter()
+            .map(|x| (Self::peer_name(ctx, &x), x))
+            .collect::<Vec<_>>();
+        if directs.is_empty() {
+            return html! {};
+        }
+        directs.sort();
+        html! {
+            <aside class="menu">
+                <p class="menu-label">{"Direct"}</p>
+                <ul class="menu-list">
+                {
+                    directs.into_iter().map(|(name, session_id)| {
+                        let key = message_list::direct_key(&session_id);
+                        let selected = self.selected_room.as_deref() == Some(key.as_str());
+                        let count = unread.get(&key).copied().unwrap_or_default();
+                        html! {
+                            <li>
+                                <a href={String::from("javascript:void(0)")} class={classes!(selected.then_some("is-active"))} onclick={ctx.link().callback(move |_| MessageDialogMsg::ClickSession(session_id.clone()))}>
+                                    {name}
+                                    {
+                                        if count > 0 && !selected {
+                                            html! {<span class="tag is-rounded is-danger menu-tag">{count.to_string()}</span>}
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                </a>
+                            </li>
+                        }
+                    }).collect::<Html>()
+                }
+                </ul>
+            </aside>
+        }
+    }
+
+    fn view_message(ctx: &Context<Self>, message: &MessageContent) -> Html {
+        let state = match &message.state {
+            SendState::Sent => html! {
+                <span class="chat-message-time">{message.time.clone()}</span>
+            },
+            SendState::Pending => html! {
+                <span class="tag is-light">{"sending"}</span>
+            },
+            SendState::Failed(err) => {
+                let client_id = message.client_id.clone().unwrap_or_default();
+                html! {
+                    <>
+                    <span class="tag is-danger is-light" title={err.clone()}>{"failed"}</span>
+                    <a href={String::from("javascript:void(0)")} class="chat-message-retry" onclick={ctx.props().on_retry.reform(move |_| client_id.clone())}>{"retry"}</a>
+                    </>
+                }
+            }
+        };
+        html! {
+            <div class={classes!("chat-message", message.is_own.map(|_| "is-own"))}>
+                <div>
+                    <strong>{message.from_name.clone()}</strong>{" "}{state}
+                </div>
+                <div class="chat-message-content">{message.content.clone()}</div>
+            </div>
+        }
+    }
+}
+
 impl Component for MessageDialog {
     type Message = MessageDialogMsg;
 
@@ -59,8 +191,10 @@ impl Component for MessageDialog {
 
     fn create(ctx: &Context<Self>) -> Self {
         let mut res = Self {
-            refs: vec![NodeRef::default(), NodeRef::default()],
+            refs: vec![NodeRef::default(), NodeRef::default(), NodeRef::default()],
             selected_room: None,
+            joining_room: None,
+            room_error: None,
         };
         if let Some(v) = &ctx.props().latest_message {
             res.selected_room = Some(v.room.clone());
@@ -78,39 +212,16 @@ impl Component for MessageDialog {
                 if e.key_code() == 13 {
                     let email_input = &self.refs[1];
                     let input = email_input.cast::<HtmlInputElement>().unwrap();
-                    let content = input.value();
-                    let messages = ctx.props().messages.clone();
                     if !e.meta_key() {
                         e.prevent_default();
+                        let content = input.value();
+                        if content.trim().is_empty() {
+                            return false;
+                        }
                         if let Some(room) = self.selected_room.as_deref() {
-                            if let Some(ws_writer) = &ctx.props().ws_writer {
-                                let w1 = ws_writer.clone();
-                                let room = room.to_string();
-                                let link = ctx.link().clone();
-                                let session_id = ctx.props().session_id.clone();
-                                spawn_local(async move {
-                                    w1.lock()
-                                        .unwrap()
-                                        .send(Message::Text(content.clone()))
-                                        .await
-                                        .unwrap();
-                                    input.set_value("");
-                                    messages.lock().unwrap().entry(room.clone()).and_modify(
-                                        |linked_list| {
-                                            linked_list.push_back(MessageContent {
-                                                id: 0,
-                                                room: room.to_string(),
-                                                from_id: "".to_string(),
-                                                from_name: session_id,
-                                                content: content.clone(),
-                                                time: "".to_string(), //chrono::Utc::now().to_default(),
-                                                is_own: Some(()),
-                                            });
-                                        },
-                                    );
-                                    link.send_message(MessageDialogMsg::Notify);
-                                });
-                            }
+                            // pending in the list until the server acks it
+                            ctx.props().on_send.emit((room.to_string(), content));
+                            input.set_value("");
                         }
                     } else {
                         input.set_value(&(input.value() + "\n"));
@@ -122,74 +233,146 @@ impl Component for MessageDialog {
                 self.selected_room = Some(name);
                 true
             }
-            MessageDialogMsg::Notify => true,
+            MessageDialogMsg::ClickSession(session_id) => {
+                if session_id == ctx.props().session_id {
+                    return false;
+                }
+                let key = message_list::direct_key(&session_id);
+                ctx.props()
+                    .messages
+                    .lock()
+                    .unwrap()
+                    .entry(key.clone())
+                    .or_insert(Default::default());
+                self.selected_room = Some(key);
+                true
+            }
+            // the room list changes when the server's join/quit frames come in
+            MessageDialogMsg::CreateRoom => {
+                if let Some(room) = self.take_room_name() {
+                    ws::send(ClientFrame::CreateRoom { room: room.clone() });
+                    self.joining_room = Some(room);
+                }
+                true
+            }
+            MessageDialogMsg::JoinRoom => {
+                if let Some(room) = self.take_room_name() {
+                    if ctx.props().rooms.lock().unwrap().contains_key(&room) {
+                        self.selected_room = Some(room);
+                    } else {
+                        ws::send(ClientFrame::JoinRoom { room: room.clone() });
+                        self.joining_room = Some(room);
+                    }
+                }
+                true
+            }
+            MessageDialogMsg::QuitRoom => {
+                match self.selected_room.take() {
+                    Some(room) if message_list::direct_peer(&room).is_none() => {
+                        ws::send(ClientFrame::QuitRoom { room });
+                    }
+                    selected => self.selected_room = selected,
+                }
+                true
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, _old_props: &Self::Properties) -> bool {
+        // an error frame (name taken) leaves it out, and the selection where it was
+        if let Some(room) = self.joining_room.take() {
+            if ctx.props().rooms.lock().unwrap().contains_key(&room) {
+                self.selected_room = Some(room);
+            } else {
+                self.joining_room = Some(room);
+            }
         }
+        true
     }
 
     fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
-        let message_input = &self.refs[0];
-        message_input
-            .cast::<HtmlInputElement>()
-            .and_then::<(), _>(|input| {
-                let msg = match self.selected_room.as_deref() {
-                    Some(room) => ctx
-                        .props()
-                        .messages
-                        .lock()
-                        .unwrap()
-                        .get(room)
-                        .unwrap_or(&Default::default())
-                        .iter()
-                        .map(|x| format!("{}: {}\n\n", x.from_name, x.content))
-                        .collect::<String>(),
-                    None => "".to_string(),
-                };
-                input.set_value(&msg);
-                input.set_scroll_top(input.scroll_height());
-                None
-            });
+        // what is on screen is read
+        if let Some(room) = self.selected_room.as_deref() {
+            if !*ctx.props().closed.lock().unwrap() {
+                ctx.props().unread.lock().unwrap().remove(room);
+            }
+        }
+        if let Some(el) = self.refs[0].cast::<web_sys::Element>() {
+            el.set_scroll_top(el.scroll_height());
+        }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         if *ctx.props().closed.lock().unwrap() {
             return html! {};
         }
-        let room_nodes = ctx
+        let mut room_nodes = ctx
             .props()
             .rooms
             .lock()
             .unwrap()
             .iter()
-            .map(|(room, _)| MenuNode {
+            .map(|(room, sessions)| MenuNode {
                 name: room.to_string(),
                 children: vec![],
+                tag: Some(sessions.len().to_string()),
             })
             .collect::<Vec<MenuNode>>();
+        room_nodes.sort_by(|a, b| a.name.cmp(&b.name));
 
         let room_labels = vec![MenuLabel {
             label: None,
             nodes: room_nodes,
         }];
 
-        let mut session_nodes = vec![];
-
-        if let Some(room) = self.selected_room.as_deref() {
-            if let Some(sessions) = ctx.props().rooms.lock().unwrap().get(room) {
-                for (_, name) in sessions.iter() {
-                    session_nodes.push(MenuNode {
-                        name: name.to_string(),
-                        children: vec![],
-                    });
+        let mut sessions = self
+            .selected_room
+            .as_deref()
+            .and_then(|x| ctx.props().rooms.lock().unwrap().get(x).cloned())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(session_id, name)| (name, session_id))
+            .collect::<Vec<_>>();
+        sessions.sort();
+        // a click opens the direct conversation with that session
+        let session_items = sessions
+            .into_iter()
+            .map(|(name, session_id)| {
+                if session_id == ctx.props().session_id {
+                    return html! { <li><a>{name}{" (you)"}</a></li> };
                 }
-            }
-        }
+                html! {
+                    <li>
+                        <a href={String::from("javascript:void(0)")} onclick={ctx.link().callback(move |_| MessageDialogMsg::ClickSession(session_id.clone()))}>{name}</a>
+                    </li>
+                }
+            })
+            .collect::<Html>();
 
-        let session_labels = vec![MenuLabel {
-            label: None,
-            nodes: session_nodes,
-        }];
+        let direct_peer = self
+            .selected_room
+            .as_deref()
+            .and_then(message_list::direct_peer);
+        let title = match direct_peer {
+            Some(session_id) => Self::peer_name(ctx, session_id),
+            None => self.selected_room.clone().unwrap_or("Dialog".to_string()),
+        };
 
-        let title = self.selected_room.as_deref().unwrap_or("Dialog");
+        let messages = match self.selected_room.as_deref() {
+            Some(room) => ctx
+                .props()
+                .messages
+                .lock()
+                .unwrap()
+                .get(room)
+                .map(|x| {
+                    x.iter()
+                        .map(|m| Self::view_message(ctx, m))
+                        .collect::<Html>()
+                })
+                .unwrap_or_default(),
+            None => html! {},
+        };
 
         html! {
             <div class="modal is-active">
@@ -202,14 +385,29 @@ impl Component for MessageDialog {
 
                     <section class="modal-card-body">
                     <div class="columns" style="height:100%;">
-                    <div class="column is-2">
-                        <div style="height: 100%; overflow: scroll;">
+                    <div class="column is-3">
+                        <div class="chat-room-form">
+                            <input ref={&self.refs[2]} class={classes!("input", "is-small", self.room_error.is_some().then_some("is-danger"))} type="text" placeholder="Room name"/>
+                            {
+                                match &self.room_error {
+                                    Some(err) => html!{<p class="help is-danger">{err}</p>},
+                                    None => html!{},
+                                }
+                            }
+                            <div class="buttons are-small">
+                                <button class="button" onclick={ctx.link().callback(|_|MessageDialogMsg::JoinRoom)}>{"Join"}</button>
+                                <button class="button" onclick={ctx.link().callback(|_|MessageDialogMsg::CreateRoom)}>{"Create"}</button>
+                                <button class="button is-danger is-light" disabled={self.selected_room.is_none() || direct_peer.is_some()} onclick={ctx.link().callback(|_|MessageDialogMsg::QuitRoom)}>{"Leave"}</button>
+                            </div>
+                        </div>
+                        <div class="chat-rooms">
                             <Menu labels = {room_labels} selected_name = {self.selected_room.clone()} on_select = {ctx.link().callback(|name|MessageDialogMsg::ClickRoom(name))}/>
+                            {self.view_directs(ctx)}
                         </div>
                     </div>
-                    <div class="column is-7">
-                        <div style="height: 70%;">
-                            <textarea ref={&self.refs[0]} style="height: 100%;" readonly={true} class="textarea has-fixed-size"></textarea>
+                    <div class="column is-6">
+                        <div ref={&self.refs[0]} class="chat-messages">
+                            {messages}
                         </div>
                         <div style="margin-top: 0.8em;">
                             <textarea ref={&self.refs[1]} class="textarea has-fixed-size" onkeydown={ctx.link().callback(|e:web_sys::KeyboardEvent|MessageDialogMsg::Send(e))} />
@@ -217,7 +415,9 @@ impl Component for MessageDialog {
                     </div>
                     <div class="column is-3">
                         <div style="height: 100%; overflow: scroll;">
-                            <Menu labels = {session_labels}/>
+                            <aside class="menu">
+                                <ul class="menu-list">{session_items}</ul>
+                            </aside>
                         </div>
                     </div>
                     </div>
@@ -19,9 +19,13 @@ pub struct MessageItemValue {
     pub from: Option<String>,
     pub content: String,
     pub room: String,
+    // shown in the header instead of the room
+    pub title: Option<String>,
     pub r#type: MessageItemType,
     // seconds
     pub timeout: Option<u32>,
+    // (label, callback) rendered as a button in the message body
+    pub action: Option<(String, Callback<()>)>,
 }
 
 #[allow(dead_code)]
@@ -49,17 +53,30 @@ impl MessageItemValue {
             id: Uuid::new_v4().as_u128(),
             r#type: r#type,
             room: room.to_string(),
+            title: None,
             content: content.to_string(),
             from_id: from_id.and_then(|x| Some(x.to_string())),
             from: from.and_then(|x| Some(x.to_string())),
             timeout: timeout,
+            action: None,
         }
     }
+
+    pub fn with_title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_string());
+        self
+    }
+
+    pub fn with_action(mut self, label: &str, on_action: Callback<()>) -> Self {
+        self.action = Some((label.to_string(), on_action));
+        self
+    }
 }
 
 pub enum MessageItemMsg {
     Close(u128),
     OpenDialog(u128),
+    Action(u128),
 }
 
 impl Component for MessageItem {
@@ -83,6 +100,13 @@ impl Component for MessageItem {
                 }
                 false
             }
+            MessageItemMsg::Action(id) => {
+                if let Some((_, on_action)) = &ctx.props().value.action {
+                    on_action.emit(());
+                }
+                ctx.props().on_close.emit(id);
+                false
+            }
         }
     }
 
@@ -120,11 +144,19 @@ impl Component for MessageItem {
         html! {
             <article class={format!{"message is-light is-small is-{}", t}}>
             <div class="message-header">
-                <p>{value.room.clone()}</p>
+                <p>{value.title.clone().unwrap_or(value.room.clone())}</p>
                 <button class="delete" aria-label="delete" onclick = {ctx.link().callback(move |_| MessageItemMsg::Close(id))}></button>
             </div>
             <div class="message-body"  style={style} onclick = {onclick}>
                {content}
+               if let Some((label, _)) = &value.action {
+                   <div class="buttons is-right">
+                       <button class={format!("button is-small is-{}", t)} onclick = {ctx.link().callback(move |e: MouseEvent| {
+                           e.stop_propagation();
+                           MessageItemMsg::Action(id)
+                       })}>{label.clone()}</button>
+                   </div>
+               }
             </div>
             </article>
         }
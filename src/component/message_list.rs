@@ -1,47 +1,41 @@
 #![allow(dead_code)]
 
 use super::message_item::{MessageItem, MessageItemType, MessageItemValue};
-use serde::Deserialize;
 use std::sync::{Arc, Mutex};
 use yew::prelude::*;
 use yew::Properties;
 
 use super::message_dialog::MessageDialog;
-use crate::util::request;
-use futures::stream::SplitSink;
-use futures::{SinkExt, StreamExt};
-use gloo_net::websocket::{futures::WebSocket, Message};
+use crate::util::common;
+use crate::util::error::ErrorKind;
+pub use crate::util::protocol::MessageContent;
+use crate::util::protocol::{ClientFrame, SendState, ServerFrame};
+use crate::util::ws;
+use gloo::timers::callback::Timeout;
 use std::collections::HashMap;
 use std::collections::LinkedList;
-use wasm_bindgen_futures::spawn_local;
 
 const DEFAULT_ROOM: &str = "main";
-const UPDATE_SESSION_PRE: &str = "update_session:";
-const LIST_PRE: &str = "list:";
-const JOIN_ROOM_PRE: &str = "join_room:";
-const QUIT_ROOM_PRE: &str = "quit_room:";
-const UPDATE_NAME_PRE: &str = "update_name:";
-const MESSAGE_PRE: &str = "message:";
-#[derive(Deserialize)]
-struct UpdateName<'a> {
-    pub session_id: &'a str,
-    pub name: &'a str,
-    pub old_name: &'a str,
-}
-
-#[derive(Deserialize, Debug)]
-struct RoomChange<'a> {
-    pub session_id: &'a str,
-    pub name: &'a str,
-    pub room: &'a str,
-}
+// direct conversations share `messages` with rooms, keyed by the peer's session id behind this
+const DIRECT_PREFIX: &str = "@";
+// a send not acked by then is shown as failed, a late ack still marks it sent
+const ACK_TIMEOUT_MILLIS: u32 = 10_000;
 
 #[derive(Default)]
 pub struct MessageList {
-    ws_writer: Option<Arc<Mutex<SplitSink<WebSocket, Message>>>>,
+    ws_subscription: Option<ws::Subscription>,
     rooms: Arc<Mutex<HashMap<String, HashMap<String, String>>>>,
+    // our session in the chat, the server assigns it; the user name until it does
     session_id: Arc<Mutex<Option<String>>>,
+    name: String,
+    // client id -> how many times it was sent, only the latest send times out
+    attempts: HashMap<String, u32>,
+    // sends waiting in the offline queue, (client id, attempt)
+    queued: Vec<(String, u32)>,
+    _ws_status_subscription: Option<ws::Subscription>,
     messages: Arc<Mutex<HashMap<String, LinkedList<MessageContent>>>>,
+    // direct conversation key -> messages not read yet
+    unread: Arc<Mutex<HashMap<String, usize>>>,
     dialog_closed: Arc<Mutex<bool>>,
     latest_message: Option<MessageContent>,
 }
@@ -66,26 +60,24 @@ pub enum MessageListMsg {
     Remove(u128),
     OpenDialog(u128),
     InitWS,
+    Frame(ServerFrame),
+    Send { room: String, content: String },
+    Retry(String),
+    AckTimeout(String, u32),
+    WsStatus(ws::WsStatus),
     ShowMsg(MessageContent),
     Notify,
 }
 
-#[derive(Deserialize, Clone, Debug)]
-pub struct MessageContent {
-    pub id: u128,
-    pub room: String,
-    pub from_id: String,
-    pub from_name: String,
-    pub content: String,
-    pub time: String,
-    pub is_own: Option<()>,
-}
-
 pub trait MessageOperate {
     fn ok(&self, msg: &str);
     fn warn(&self, msg: &str);
     fn info(&self, msg: &str);
     fn error(&self, msg: &str);
+    /// `error` for a failed request, unless the response already left the page
+    fn request_failed(&self, err: &(dyn std::error::Error + 'static));
+    /// a warning that stays until closed or its action is taken
+    fn prompt(&self, msg: &str, action: &str, on_action: Callback<()>);
     fn message(&self, room: &str, from_id: &str, from_name: &str, content: &str);
 }
 
@@ -146,6 +138,19 @@ impl MessageOperate for Arc<Mutex<MessageListValue>> {
             MessageItemValue::new(MessageItemType::Danger, "Error", msg, Some(10), None, None),
         )
     }
+    fn request_failed(&self, err: &(dyn std::error::Error + 'static)) {
+        if matches!(err.downcast_ref::<ErrorKind>(), Some(x) if x.is_redirected()) {
+            return;
+        }
+        self.error(&format!("{}", err))
+    }
+    fn prompt(&self, msg: &str, action: &str, on_action: Callback<()>) {
+        push(
+            self,
+            MessageItemValue::new(MessageItemType::Warning, "Warning", msg, None, None, None)
+                .with_action(action, on_action),
+        )
+    }
 
     fn message(&self, room: &str, from_id: &str, from_name: &str, content: &str) {
         push(
@@ -162,6 +167,69 @@ impl MessageOperate for Arc<Mutex<MessageListValue>> {
     }
 }
 
+/// the `messages` key of the direct conversation with `session_id`
+pub fn direct_key(session_id: &str) -> String {
+    format!("{}{}", DIRECT_PREFIX, session_id)
+}
+
+/// the peer's session id when `key` is a direct conversation
+pub fn direct_peer(key: &str) -> Option<&str> {
+    key.strip_prefix(DIRECT_PREFIX)
+}
+
+impl MessageList {
+    /// apply `f` to our own message sent as `client_id`, false when there is none
+    fn update_own<F>(&self, client_id: &str, f: F) -> bool
+    where
+        F: FnOnce(&mut MessageContent),
+    {
+        let mut messages = self.messages.lock().unwrap();
+        let message = messages
+            .values_mut()
+            .flat_map(|x| x.iter_mut())
+            .find(|x| x.client_id.as_deref() == Some(client_id));
+        match message {
+            Some(v) => {
+                f(v);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn send(&mut self, ctx: &Context<Self>, room: String, content: String, client_id: String) {
+        let attempt = self.attempts.entry(client_id.clone()).or_insert(0);
+        *attempt += 1;
+        let attempt = *attempt;
+        let frame = match direct_peer(&room) {
+            Some(to) => ClientFrame::Direct {
+                to: to.to_string(),
+                content,
+                client_id: client_id.clone(),
+            },
+            None => ClientFrame::Send {
+                room,
+                content,
+                client_id: client_id.clone(),
+            },
+        };
+        if ws::send(frame) {
+            Self::wait_ack(ctx, client_id, attempt);
+        } else {
+            // the clock starts once the frame leaves the offline queue
+            self.queued.push((client_id, attempt));
+        }
+    }
+
+    fn wait_ack(ctx: &Context<Self>, client_id: String, attempt: u32) {
+        let link = ctx.link().clone();
+        Timeout::new(ACK_TIMEOUT_MILLIS, move || {
+            link.send_message(MessageListMsg::AckTimeout(client_id, attempt))
+        })
+        .forget();
+    }
+}
+
 impl Component for MessageList {
     type Message = MessageListMsg;
 
@@ -169,10 +237,15 @@ impl Component for MessageList {
 
     fn create(_ctx: &Context<Self>) -> Self {
         Self {
-            ws_writer: None,
+            ws_subscription: None,
             session_id: Default::default(),
+            name: Default::default(),
+            attempts: Default::default(),
+            queued: Default::default(),
+            _ws_status_subscription: None,
             rooms: Default::default(),
             messages: Default::default(),
+            unread: Default::default(),
             dialog_closed: Arc::new(Mutex::new(true)),
             latest_message: None,
         }
@@ -198,6 +271,9 @@ impl Component for MessageList {
                     content: item.content,
                     time: "".to_string(),
                     is_own: None,
+                    to: None,
+                    client_id: None,
+                    state: SendState::Sent,
                 });
 
                 for msg in value.iter() {
@@ -207,156 +283,218 @@ impl Component for MessageList {
                 true
             }
             MessageListMsg::InitWS => {
-                let ws = request::open_ws().unwrap();
+                let user = match common::get_current_user() {
+                    Ok(v) => v,
+                    Err(err) => {
+                        log::error!("get current user error: {}", err);
+                        return false;
+                    }
+                };
+                self.name = user.name.clone().unwrap_or(user.email.clone());
+                let mut sid = self.session_id.lock().unwrap();
+                if sid.is_none() {
+                    *sid = Some(self.name.clone());
+                }
+                let mut sr = self.rooms.lock().unwrap();
+                if sr.is_empty() {
+                    sr.insert(DEFAULT_ROOM.to_string(), HashMap::new());
+                }
+                self.ws_subscription =
+                    Some(ws::subscribe(ctx.link().callback(MessageListMsg::Frame)));
+                self._ws_status_subscription = Some(ws::subscribe_status(
+                    ctx.link().callback(MessageListMsg::WsStatus),
+                ));
+                // the connection outlives the page, greet only when it is opened
+                if ws::connect() {
+                    ws::send(ClientFrame::send(DEFAULT_ROOM, "i am back online!"));
+                }
+                true
+            }
+            MessageListMsg::Frame(frame) => match frame {
+                ServerFrame::Message(mut message) => {
+                    // our own send coming back, it takes the place of the pending one
+                    if let Some(client_id) = message.client_id.as_deref() {
+                        self.attempts.remove(client_id);
+                        let stored = message.clone();
+                        if self.update_own(client_id, move |x| {
+                            x.id = stored.id;
+                            x.time = stored.time;
+                            x.state = SendState::Sent;
+                        }) {
+                            return true;
+                        }
+                    }
+                    // a direct message goes to the conversation with the other side
+                    if let Some(to) = message.to.clone() {
+                        let own =
+                            Some(&message.from_id) == self.session_id.lock().unwrap().as_ref();
+                        let peer = if own { to } else { message.from_id.clone() };
+                        message.room = direct_key(&peer);
+                        if !own {
+                            *self
+                                .unread
+                                .lock()
+                                .unwrap()
+                                .entry(message.room.clone())
+                                .or_insert(0) += 1;
+                        }
+                    }
+                    self.messages
+                        .lock()
+                        .unwrap()
+                        .entry(message.room.clone())
+                        .or_insert(Default::default())
+                        .push_back(message.clone());
+                    if *self.dialog_closed.lock().unwrap() {
+                        ctx.link().send_message(MessageListMsg::ShowMsg(message));
+                        return false;
+                    }
+                    true
+                }
+                ServerFrame::UpdateSession { session_id } => {
+                    *self.session_id.lock().unwrap() = Some(session_id);
+                    false
+                }
+                ServerFrame::List { rooms } => {
+                    *self.rooms.lock().unwrap() = rooms;
+                    true
+                }
+                ServerFrame::JoinRoom(change) => {
+                    let mut sr = self.rooms.lock().unwrap();
 
-                let (writer, mut reader) = ws.split();
+                    if Some(&change.session_id) == self.session_id.lock().unwrap().as_ref() {
+                        sr.entry(change.room.clone()).or_insert(HashMap::new());
+                    }
 
-                self.ws_writer = Some(Arc::new(Mutex::new(writer)));
-                match self.ws_writer {
-                    Some(ref w) => {
-                        let w1 = Arc::clone(w);
-                        spawn_local(async move {
-                            w1.lock()
-                                .unwrap()
-                                .send(Message::Text(String::from("i am back online!")))
-                                .await
-                                .unwrap();
-                        });
+                    if let Some(hm) = sr.get_mut(&change.room) {
+                        hm.insert(change.session_id, change.name);
                     }
-                    None => (),
+                    true
                 }
+                ServerFrame::QuitRoom(change) => {
+                    let mut sr = self.rooms.lock().unwrap();
 
-                let link = ctx.link().clone();
-                let self_messages = self.messages.clone();
-                let self_rooms = self.rooms.clone();
-                let session_id = self.session_id.clone();
-                let session_id_c = session_id.clone();
-                let mut sid = session_id_c.lock().unwrap();
-                if sid.is_none() {
-                    let user = &crate::util::common::get_current_user().unwrap();
-                    *sid = Some(user.name.clone().unwrap_or(user.email.clone()));
+                    if Some(&change.session_id) == self.session_id.lock().unwrap().as_ref() {
+                        sr.remove(&change.room);
+                    } else if let Some(hm) = sr.get_mut(&change.room) {
+                        hm.remove(&change.session_id);
+                    }
+                    true
                 }
-                let sr1 = self_rooms.clone();
-                let mut sr = sr1.lock().unwrap();
-                if sr.is_empty() {
-                    sr.insert(DEFAULT_ROOM.to_string(), HashMap::new());
+                ServerFrame::UpdateName {
+                    session_id, name, ..
+                } => {
+                    for (_, sessions) in self.rooms.lock().unwrap().iter_mut() {
+                        sessions
+                            .entry(session_id.clone())
+                            .and_modify(|x| *x = name.clone());
+                    }
+                    true
+                }
+                ServerFrame::Ack {
+                    client_id,
+                    id,
+                    time,
+                } => {
+                    self.attempts.remove(&client_id);
+                    self.update_own(&client_id, move |x| {
+                        x.id = id;
+                        x.time = time;
+                        x.state = SendState::Sent;
+                    })
                 }
-                let dialog_closed = self.dialog_closed.clone();
-                spawn_local(async move {
-                    while let Some(msg) = reader.next().await {
-                        match msg {
-                            Ok(msg) => {
-                                // 【{room}】{name}: {msg}
-                                if let Message::Text(content) = msg {
-                                    if content.starts_with(MESSAGE_PRE) {
-                                        let message: MessageContent = serde_json::from_str(
-                                            content.trim_start_matches(MESSAGE_PRE),
-                                        )
-                                        .unwrap();
-                                        self_messages
-                                            .lock()
-                                            .unwrap()
-                                            .entry(message.room.clone())
-                                            .or_insert(Default::default())
-                                            .push_back(message.clone());
-                                        if *dialog_closed.lock().unwrap() {
-                                            link.send_message(MessageListMsg::ShowMsg(message));
-                                        } else {
-                                            link.send_message(MessageListMsg::Notify);
-                                        }
-                                    } else {
-                                        if content.starts_with(LIST_PRE) {
-                                            let rooms: HashMap<String, HashMap<String, String>> =
-                                                serde_json::from_str(
-                                                    content.trim_start_matches(LIST_PRE),
-                                                )
-                                                .unwrap();
-
-                                            *self_rooms.lock().unwrap() = rooms;
-                                        } else if content.starts_with(JOIN_ROOM_PRE) {
-                                            let change: RoomChange = serde_json::from_str(
-                                                content.trim_start_matches(JOIN_ROOM_PRE),
-                                            )
-                                            .unwrap();
-                                            let mut sr = self_rooms.lock().unwrap();
-
-                                            if change.session_id
-                                                == session_id.lock().unwrap().as_deref().unwrap()
-                                            {
-                                                sr.entry(change.room.to_string())
-                                                    .or_insert(HashMap::new());
-                                            }
-
-                                            if let Some(hm) = sr.get_mut(change.room) {
-                                                hm.insert(
-                                                    change.session_id.to_string(),
-                                                    change.name.to_string(),
-                                                );
-                                            }
-                                        } else if content.starts_with(QUIT_ROOM_PRE) {
-                                            let change: RoomChange = serde_json::from_str(
-                                                content.trim_start_matches(QUIT_ROOM_PRE),
-                                            )
-                                            .unwrap();
-
-                                            let mut sr = self_rooms.lock().unwrap();
-
-                                            if change.session_id
-                                                == session_id.lock().unwrap().as_deref().unwrap()
-                                            {
-                                                sr.remove(change.room);
-                                            } else {
-                                                if let Some(hm) = sr.get_mut(change.room) {
-                                                    hm.remove(change.session_id);
-                                                }
-                                            }
-                                        } else if content.starts_with(UPDATE_NAME_PRE) {
-                                            let change: UpdateName = serde_json::from_str(
-                                                content.trim_start_matches(UPDATE_NAME_PRE),
-                                            )
-                                            .unwrap();
-
-                                            for (_, sessions) in
-                                                self_rooms.lock().unwrap().iter_mut()
-                                            {
-                                                sessions
-                                                    .entry(change.session_id.to_string())
-                                                    .and_modify(|x| *x = change.name.to_string());
-                                            }
-                                        }
-                                        link.send_message(MessageListMsg::Notify);
-                                    }
-                                }
-                            }
-                            Err(err) => match err {
-                                gloo_net::websocket::WebSocketError::ConnectionError => {
-                                    log::error!("connection error: {:#?}", err);
-                                    break;
-                                }
-                                gloo_net::websocket::WebSocketError::ConnectionClose(e) => {
-                                    log::info!("connection closed, close event: {:#?}", e);
-                                    break;
-                                }
-                                gloo_net::websocket::WebSocketError::MessageSendError(e) => {
-                                    log::error!("message send error: {:#?}", e);
-                                }
-                                _ => {
-                                    log::error!("read error: {:#?}", err);
-                                }
-                            },
+                ServerFrame::Error { client_id, msg } => {
+                    let failed = match client_id.as_deref() {
+                        Some(client_id) => {
+                            let state = SendState::Failed(msg.clone());
+                            self.update_own(client_id, move |x| x.state = state)
                         }
+                        None => false,
+                    };
+                    if !failed {
+                        ctx.props().value.error(&msg);
+                    }
+                    true
+                }
+                ServerFrame::Pong => false,
+            },
+            MessageListMsg::Send { room, content } => {
+                let client_id = uuid::Uuid::new_v4().to_string();
+                let from_id = self.session_id.lock().unwrap().clone().unwrap_or_default();
+                self.messages
+                    .lock()
+                    .unwrap()
+                    .entry(room.clone())
+                    .or_insert(Default::default())
+                    .push_back(MessageContent {
+                        id: 0,
+                        room: room.clone(),
+                        from_id,
+                        from_name: self.name.clone(),
+                        content: content.clone(),
+                        time: "".to_string(),
+                        is_own: Some(()),
+                        to: direct_peer(&room).map(|x| x.to_string()),
+                        client_id: Some(client_id.clone()),
+                        state: SendState::Pending,
+                    });
+                self.send(ctx, room, content, client_id);
+                true
+            }
+            MessageListMsg::Retry(client_id) => {
+                // still waiting to go out, a retry would only queue a second copy
+                if ws::is_queued(&client_id) {
+                    return false;
+                }
+                let mut retry = None;
+                self.update_own(&client_id, |x| {
+                    if let SendState::Failed(_) = x.state {
+                        x.state = SendState::Pending;
+                        retry = Some((x.room.clone(), x.content.clone()));
                     }
                 });
-
+                match retry {
+                    // same client id, the server keeps one copy if the first try got through
+                    Some((room, content)) => {
+                        self.send(ctx, room, content, client_id);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            // the queue is flushed by now, the queued sends wait for their acks from here
+            MessageListMsg::WsStatus(ws::WsStatus::Online) => {
+                for (client_id, attempt) in std::mem::take(&mut self.queued) {
+                    Self::wait_ack(ctx, client_id, attempt);
+                }
                 false
             }
+            MessageListMsg::WsStatus(_) => false,
+            MessageListMsg::AckTimeout(client_id, attempt) => {
+                if self.attempts.get(&client_id) != Some(&attempt) {
+                    return false;
+                }
+                self.update_own(&client_id, |x| {
+                    if x.state == SendState::Pending {
+                        x.state = SendState::Failed("no answer from the server".to_string());
+                    }
+                })
+            }
             MessageListMsg::ShowMsg(message) => {
-                ctx.props().value.message(
+                // the room still opens the conversation, a direct one's key is no title
+                let item = MessageItemValue::new(
+                    MessageItemType::Primary,
                     &message.room,
-                    &message.from_id,
-                    &message.from_name,
                     &message.content,
+                    None,
+                    Some(&message.from_name),
+                    Some(&message.from_id),
                 );
+                let item = match direct_peer(&message.room) {
+                    Some(_) => item.with_title(&format!("Direct: {}", message.from_name)),
+                    None => item,
+                };
+                push(&ctx.props().value, item);
                 true
             }
             MessageListMsg::Notify => true,
@@ -382,10 +520,10 @@ impl Component for MessageList {
             </div>
 
             {
-                if self.ws_writer.is_some() && !*self.dialog_closed.lock().unwrap(){
+                if self.ws_subscription.is_some() && !*self.dialog_closed.lock().unwrap(){
                     let session_id = self.session_id.lock().unwrap().clone().unwrap();
                     html!{
-                        <MessageDialog session_id={session_id} rooms={self.rooms.clone()} messages={self.messages.clone()}  ws_writer = {self.ws_writer.clone()} closed = {self.dialog_closed.clone()} latest_message = {self.latest_message.clone()}/>
+                        <MessageDialog session_id={session_id} rooms={self.rooms.clone()} messages={self.messages.clone()} unread={self.unread.clone()} closed = {self.dialog_closed.clone()} latest_message = {self.latest_message.clone()} on_send = {ctx.link().callback(|(room, content)|MessageListMsg::Send{room, content})} on_retry = {ctx.link().callback(MessageListMsg::Retry)}/>
                     }
                 }else{
                     html!{}
@@ -1,3 +1,4 @@
+pub mod in_flight;
 pub mod message_item;
 pub mod message_list;
 pub mod pager;
@@ -0,0 +1,146 @@
+use crate::util::common;
+use crate::util::error::ErrorKind;
+use crate::util::session;
+use std::cell::RefCell;
+use yew::prelude::*;
+
+const TITLE: &str = "Something went wrong";
+const SUBTITLE: &str = "The page can not continue. Reload it, or log in again if it keeps failing.";
+
+thread_local! {
+    static REPORTER: RefCell<Option<Callback<ErrorKind>>> = RefCell::new(None);
+}
+
+/// hand an error the current page can not recover from to the boundary around the app
+pub fn raise(err: ErrorKind) {
+    log::error!("unrecoverable error: {}", err);
+    let reporter = REPORTER.with(|x| x.borrow().clone());
+    match reporter {
+        Some(reporter) => reporter.emit(err),
+        None => render_static(&err.to_string()),
+    }
+}
+
+/// a panic leaves yew's scheduler in an unknown state, so the error screen is written
+/// straight into the document instead of going through the boundary component.
+/// Registered with yew, `Renderer::render` would replace a plain `std::panic` hook;
+/// call it before the renderer is created.
+pub fn install_panic_hook() {
+    yew::set_custom_panic_hook(Box::new(|info| {
+        log::error!("{}", info);
+        render_static(&info.to_string());
+    }));
+}
+
+fn render_static(msg: &str) {
+    let document = match web_sys::window().and_then(|x| x.document()) {
+        Some(v) => v,
+        None => return,
+    };
+    let body = match document.body() {
+        Some(v) => v,
+        None => return,
+    };
+    let reload = common::current_location();
+    let login = common::with_next("/login", &reload);
+    body.set_inner_html(&format!(
+        r#"<section class="hero is-bold is-large error-boundary">
+            <div class="hero-body">
+                <div class="container">
+                    <h1 class="title">{}</h1>
+                    <h2 class="subtitle">{}</h2>
+                    <pre class="error-boundary-msg"></pre>
+                    <div class="buttons">
+                        <a class="button is-primary" href="{}">Reload</a>
+                        <a class="button is-light" href="{}">Log in again</a>
+                    </div>
+                </div>
+            </div>
+        </section>"#,
+        TITLE, SUBTITLE, reload, login
+    ));
+    // as text, the message is not markup
+    if let Ok(Some(el)) = document.query_selector(".error-boundary-msg") {
+        el.set_text_content(Some(msg));
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorBoundaryProps {
+    pub children: Children,
+}
+
+/// Renders its children until an unrecoverable error is raised, then a screen to reload or log in again.
+pub struct ErrorBoundary {
+    error: Option<ErrorKind>,
+}
+
+pub enum ErrorBoundaryMsg {
+    Raise(ErrorKind),
+    Reload,
+    Relogin,
+}
+
+impl Component for ErrorBoundary {
+    type Message = ErrorBoundaryMsg;
+
+    type Properties = ErrorBoundaryProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let reporter = ctx.link().callback(ErrorBoundaryMsg::Raise);
+        REPORTER.with(|x| *x.borrow_mut() = Some(reporter));
+        Self { error: None }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ErrorBoundaryMsg::Raise(err) => {
+                // keep the first one, the rest usually follow from it
+                if self.error.is_none() {
+                    self.error = Some(err);
+                }
+                true
+            }
+            ErrorBoundaryMsg::Reload => {
+                if let Some(window) = web_sys::window() {
+                    if let Err(err) = window.location().reload() {
+                        log::error!("reload error: {:?}", err);
+                    }
+                }
+                false
+            }
+            ErrorBoundaryMsg::Relogin => {
+                self.error = None;
+                session::logout();
+                true
+            }
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        REPORTER.with(|x| *x.borrow_mut() = None);
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        match &self.error {
+            Some(err) => html! {
+                <section class="hero is-bold is-large error-boundary">
+                    <div class="hero-body">
+                        <div class="container">
+                            <h1 class="title">{TITLE}</h1>
+                            <h2 class="subtitle">{SUBTITLE}</h2>
+                            <pre class="error-boundary-msg">{err.to_string()}</pre>
+                            <div class="buttons">
+                                <button class="button is-primary" onclick={ctx.link().callback(|_|ErrorBoundaryMsg::Reload)}>{"Reload"}</button>
+                                <button class="button is-light" onclick={ctx.link().callback(|_|ErrorBoundaryMsg::Relogin)}>{"Log in again"}</button>
+                            </div>
+                        </div>
+                    </div>
+                </section>
+            },
+            None => html! {
+                <>{ctx.props().children.clone()}</>
+            },
+        }
+    }
+}
@@ -1,2 +1,3 @@
+pub mod error_boundary;
+pub mod page_not_found;
 pub mod request_error;
-pub mod page_not_found;
\ No newline at end of file
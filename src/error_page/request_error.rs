@@ -1,4 +1,7 @@
+use crate::util::common;
+use crate::Route;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 #[derive(PartialEq, Properties)]
 pub struct RequestProps {
@@ -7,6 +10,12 @@ pub struct RequestProps {
 
 #[function_component(RequestError)]
 pub fn request_error(props: &RequestProps) -> Html {
+    // hand the page that got the 401 over to login
+    let next_query = use_location()
+        .and_then(|x| x.query::<common::NextQuery>().ok())
+        .and_then(|x| x.next)
+        .and_then(|x| common::validate_next(&x))
+        .map(|x| common::NextQuery { next: Some(x) });
     match props.status {
         401 => html! {
         <html lang="en">
@@ -22,7 +31,25 @@ pub fn request_error(props: &RequestProps) -> Html {
                 <hr/>
             </div>
             <div role="contentinfo" align="center">
-                <span style="margin-top:15px;"><a href="/login">{"Go to login"}</a></span>
+                <span style="margin-top:15px;"><Link<Route, common::NextQuery> to={Route::Login} query={next_query}>{"Go to login"}</Link<Route, common::NextQuery>></span>
+            </div>
+        </body>
+        </html>
+            },
+        403 => html! {
+        <html lang="en">
+        <head>
+            <meta charset="utf-8"/>
+            <title>{"403 Forbidden"}</title>
+        </head>
+        <body align="center">
+            <div role="main" align="center">
+                <h1>{"403: Forbidden"}</h1>
+                <p>{"You don't have permission to access this page."}</p>
+                <hr/>
+            </div>
+            <div role="contentinfo" align="center">
+                <span style="margin-top:15px;"><Link<Route> to={Route::Welcome}>{"Go to home"}</Link<Route>></span>
             </div>
         </body>
         </html>
@@ -41,7 +68,7 @@ pub fn request_error(props: &RequestProps) -> Html {
                 <hr/>
             </div>
             <div role="contentinfo" align="center">
-                <span style="margin-top:15px;"><a href="/login">{"Go to login"}</a></span>
+                <span style="margin-top:15px;"><Link<Route> to={Route::Login}>{"Go to login"}</Link<Route>></span>
             </div>
         </body>
         </html>
@@ -58,7 +85,7 @@ pub fn request_error(props: &RequestProps) -> Html {
                 <hr/>
             </div>
             <div role="contentinfo" align="center">
-                <span style="margin-top:15px;"><a href="/login">{"Go to login"}</a></span>
+                <span style="margin-top:15px;"><Link<Route> to={Route::Login}>{"Go to login"}</Link<Route>></span>
             </div>
         </body>
         </html>
@@ -1,11 +1,19 @@
+use crate::component::in_flight::InFlightState;
 use crate::util::common;
+use crate::util::debounce::{Debounce, Generation};
+use crate::util::error::ErrorKind;
+use crate::util::request::{self, InFlight};
+use crate::Route;
 use gloo::timers::callback::Timeout;
 use serde::Serialize;
 use user_cli::apis::user_controller_api;
 use user_cli::models;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
+// in-flight key of the calls behind the reset button
+const SUBMIT_KEY: &str = "forget_pwd";
 const DEFAULT_CODE_BUTTON_TEXT: &str = "Generate Code";
 const DEFAULT_CODE_BUTTON_CLASS: &str =
     "button is-block is-fullwidth is-primary is-medium is-rounded";
@@ -44,6 +52,9 @@ pub struct ForgetPwd {
     code_button_class: String,
     password_confirm: String,
     code_fail_msg: String,
+    email_debounce: Debounce,
+    email_generation: Generation,
+    in_flight: InFlightState,
 }
 
 pub enum ValidateExistEmailOperation {
@@ -54,8 +65,8 @@ pub enum ValidateExistEmailOperation {
 
 pub enum ForgetPwdMsg {
     ValidateExistEmail(ValidateExistEmailOperation),
-    ValidateExistEmailSuccess(ValidateExistEmailOperation),
-    ValidateExistEmailFail(Box<dyn std::error::Error>),
+    ValidateExistEmailSuccess(u64, ValidateExistEmailOperation),
+    ValidateExistEmailFail(u64, Box<dyn std::error::Error>),
     EmailChange(web_sys::KeyboardEvent),
     PasswordChange(web_sys::KeyboardEvent),
     PasswordConfirmChange(web_sys::KeyboardEvent),
@@ -66,6 +77,7 @@ pub enum ForgetPwdMsg {
     HandleSendEmailCodeSuccess(usize),
     HandleSendEmailCodeError(Box<dyn std::error::Error>),
     HandleSendEmailCodeHint(Box<dyn std::error::Error>),
+    InFlightChange(InFlight),
 }
 
 impl Component for ForgetPwd {
@@ -84,7 +96,8 @@ impl Component for ForgetPwd {
         }
     }
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let in_flight = InFlightState::subscribe(ctx, ForgetPwdMsg::InFlightChange);
         Self {
             refs: vec![NodeRef::default()],
             req: Default::default(),
@@ -99,22 +112,42 @@ impl Component for ForgetPwd {
             password_confirm: Default::default(),
             code_fail_msg: Default::default(),
             code_button_class: DEFAULT_CODE_BUTTON_CLASS.to_string(),
+            email_debounce: Default::default(),
+            email_generation: Default::default(),
+            in_flight,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             ForgetPwdMsg::ValidateExistEmail(operation) => {
+                if let ValidateExistEmailOperation::ResetPwd = operation {
+                    if self.in_flight.is_pending(SUBMIT_KEY) {
+                        return false;
+                    }
+                }
+                self.email_debounce.cancel();
+                let generation = self.email_generation.next();
                 let email = self.req.email.clone();
                 ctx.link().send_future(async move {
-                    match common::validate_exist_email(&email).await {
-                        Ok(_) => ForgetPwdMsg::ValidateExistEmailSuccess(operation),
-                        Err(err) => ForgetPwdMsg::ValidateExistEmailFail(Box::new(err)),
+                    let call = common::validate_exist_email(&email);
+                    let res = match &operation {
+                        ValidateExistEmailOperation::ResetPwd => {
+                            request::track_as(SUBMIT_KEY, call).await
+                        }
+                        _ => call.await,
+                    };
+                    match res {
+                        Ok(_) => ForgetPwdMsg::ValidateExistEmailSuccess(generation, operation),
+                        Err(err) => ForgetPwdMsg::ValidateExistEmailFail(generation, Box::new(err)),
                     }
                 });
                 false
             }
-            ForgetPwdMsg::ValidateExistEmailSuccess(operation) => {
+            ForgetPwdMsg::ValidateExistEmailSuccess(generation, operation) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::Valid;
                 self.code_button_disabled = false;
                 match operation {
@@ -144,9 +177,18 @@ impl Component for ForgetPwd {
                             pwd: req.pwd,
                         };
                         ctx.link().send_future(async move {
-                            match user_controller_api::change_pwd(
-                                &common::get_cli_config_without_token().unwrap(),
-                                req,
+                            let config = match common::get_cli_config_without_token() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    return ForgetPwdMsg::HandleChangePwdError(Box::new(err))
+                                }
+                            };
+                            match request::track_as(
+                                SUBMIT_KEY,
+                                request::cli_anonymous(
+                                    "change_pwd",
+                                    user_controller_api::change_pwd(&config, req),
+                                ),
                             )
                             .await
                             {
@@ -164,43 +206,54 @@ impl Component for ForgetPwd {
 
                         self.code_button_class = "button is-block is-fullwidth is-primary is-medium is-rounded is-loading".to_string();
                         ctx.link().send_future(async move {
-                            match user_controller_api::send_email_code(
-                                &common::get_cli_config_without_token().unwrap(),
-                                req,
+                            let config = match common::get_cli_config_without_token() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    return ForgetPwdMsg::HandleSendEmailCodeError(Box::new(err))
+                                }
+                            };
+                            match request::cli_anonymous(
+                                "send_email_code",
+                                user_controller_api::send_email_code(&config, req),
                             )
                             .await
                             {
                                 Ok(res) => {
                                     ForgetPwdMsg::HandleSendEmailCodeSuccess(res.data as usize)
                                 }
-                                Err(err) => match err {
-                                    user_cli::apis::Error::ResponseError(ref f) => {
-                                        if f.status.as_u16() == 452 {
-                                            // hint
-                                            ForgetPwdMsg::HandleSendEmailCodeHint(Box::new(err))
-                                        } else {
-                                            ForgetPwdMsg::HandleSendEmailCodeError(Box::new(err))
-                                        }
+                                Err(err) => {
+                                    if err.is_hint() {
+                                        ForgetPwdMsg::HandleSendEmailCodeHint(Box::new(err))
+                                    } else {
+                                        ForgetPwdMsg::HandleSendEmailCodeError(Box::new(err))
                                     }
-                                    _ => ForgetPwdMsg::HandleSendEmailCodeError(Box::new(err)),
-                                },
+                                }
                             }
                         });
                     }
                 }
                 true
             }
-            ForgetPwdMsg::ValidateExistEmailFail(e) => {
+            ForgetPwdMsg::ValidateExistEmailFail(generation, e) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::InValid(format!("{}", e));
                 self.code_button_disabled = true;
                 true
             }
             ForgetPwdMsg::EmailChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                if el.value() == self.req.email {
+                    return false;
+                }
                 self.req.email = el.value();
-                ctx.link().send_message(ForgetPwdMsg::ValidateExistEmail(
-                    ValidateExistEmailOperation::Nothing,
-                ));
+                let link = ctx.link().clone();
+                self.email_debounce.call(move || {
+                    link.send_message(ForgetPwdMsg::ValidateExistEmail(
+                        ValidateExistEmailOperation::Nothing,
+                    ))
+                });
                 true
             }
             ForgetPwdMsg::CodeChange(e) => {
@@ -232,13 +285,28 @@ impl Component for ForgetPwd {
                 true
             }
 
+            ForgetPwdMsg::InFlightChange(v) => self.in_flight.update(v, SUBMIT_KEY),
             ForgetPwdMsg::HandleChangePwdSuccess => {
-                common::set_local_storage("email", self.req.email.as_str());
-                common::set_local_storage("pwd", self.req.pwd.as_str());
-                common::redirect("/login");
+                // prefills the login, kept across restarts only with "remember me"
+                common::set_session_item("email", self.req.email.as_str());
+                if let Some(navigator) = ctx.link().navigator() {
+                    navigator.push(&Route::Login);
+                }
                 false
             }
             ForgetPwdMsg::HandleChangePwdError(e) => {
+                // the backend may refuse a single input, show it next to that one
+                if let Some(err) = e.downcast_ref::<ErrorKind>() {
+                    for (name, valid) in [
+                        ("email", &mut self.email_valid),
+                        ("pwd", &mut self.pwd_valid),
+                        ("code", &mut self.code_valid),
+                    ] {
+                        if let Some(msg) = err.field(name) {
+                            *valid = ValidStatus::InValid(msg.to_string());
+                        }
+                    }
+                }
                 self.request_fail_msg = format!("{}", e);
                 true
             }
@@ -272,6 +340,9 @@ impl Component for ForgetPwd {
             }
             ForgetPwdMsg::HandleSendEmailCodeError(e) => {
                 self.code_button_class = DEFAULT_CODE_BUTTON_CLASS.to_string();
+                if let Some(msg) = e.downcast_ref::<ErrorKind>().and_then(|x| x.field("email")) {
+                    self.email_valid = ValidStatus::InValid(msg.to_string());
+                }
                 self.code_fail_msg = format!("{}", e);
                 true
             }
@@ -323,6 +394,7 @@ impl Component for ForgetPwd {
             }
             ValidStatus::None => "input is-info is-medium is-rounded",
         };
+        let submitting = self.in_flight.is_pending(SUBMIT_KEY);
         html! {
             <>
             <header>
@@ -400,7 +472,7 @@ impl Component for ForgetPwd {
                         <br/>
                         <div class="field">
                             <p class="control">
-                                <button class="button is-block is-fullwidth is-primary is-medium is-rounded" onclick={ctx.link().callback(|_|ForgetPwdMsg::ValidateExistEmail(ValidateExistEmailOperation::ResetPwd))}>
+                                <button class={classes!("button", "is-block", "is-fullwidth", "is-primary", "is-medium", "is-rounded", submitting.then_some("is-loading"))} disabled={submitting} onclick={ctx.link().callback(|_|ForgetPwdMsg::ValidateExistEmail(ValidateExistEmailOperation::ResetPwd))}>
                                 {"Reset Password"}
                                 </button>
                             </p>
@@ -412,7 +484,7 @@ impl Component for ForgetPwd {
                         <nav class="level">
                         <div class="level-item has-text-centered">
                             <div>
-                            <a href="/login">{"Return to login"}</a>
+                            <Link<Route> to={Route::Login}>{"Return to login"}</Link<Route>>
                             </div>
                         </div>
                         </nav>
@@ -1,15 +1,24 @@
+use crate::error_page::error_boundary;
 use crate::layout::navbar::Navbar;
 use crate::util::common;
+use crate::util::config;
+use crate::util::session;
+use crate::util::ws::{self, WsStatus};
+use crate::{Route, RouteBody};
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 pub struct Header {
     navbar_active: bool,
     current_user: Option<common::CurrentUser>,
+    ws_status: WsStatus,
+    _ws_subscription: Option<ws::Subscription>,
 }
 
 pub enum HeaderMsg {
     Logout,
     ToggleNavbarActive,
+    WsStatus(WsStatus),
 }
 #[derive(Properties, PartialEq)]
 pub struct HeaderProps {
@@ -24,18 +33,34 @@ impl Component for Header {
 
     type Properties = HeaderProps;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
         let mut res = Self {
             navbar_active: false,
             current_user: Default::default(),
+            ws_status: ws::status(),
+            _ws_subscription: None,
         };
+        if config::get().features.chat {
+            res._ws_subscription = Some(ws::subscribe_status(
+                ctx.link().callback(HeaderMsg::WsStatus),
+            ));
+        }
 
         match common::get_current_user() {
             Ok(v) => res.current_user = Some(v),
+            // a token without a readable user, nothing on this page can work
+            Err(err) if common::get_token().is_ok() => error_boundary::raise(err),
             Err(err) => {
                 log::warn!("get current user error: {}", err);
                 // common::redirect("/401");
-                common::redirect("/login");
+                if let Some(navigator) = ctx.link().navigator() {
+                    let query = common::NextQuery {
+                        next: common::validate_next(&common::current_location()),
+                    };
+                    if let Err(err) = navigator.push_with_query(&Route::Login, &query) {
+                        log::error!("push query error: {}", err);
+                    }
+                }
             }
         }
         res
@@ -44,29 +69,48 @@ impl Component for Header {
     fn update(&mut self, _: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             HeaderMsg::Logout => {
-                common::delete_current_user().unwrap_or_else(|x| {
-                    log::error!("{:?}", x);
-                });
-                common::redirect("/login");
+                session::logout();
                 true
             }
             HeaderMsg::ToggleNavbarActive => {
                 self.navbar_active = !self.navbar_active;
                 true
             }
+            HeaderMsg::WsStatus(status) => {
+                self.ws_status = status;
+                true
+            }
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let user = self.current_user.clone().unwrap();
+        // on the way to login
+        let user = match self.current_user.clone() {
+            Some(v) => v,
+            None => return html! {},
+        };
         let navbar_active = if self.navbar_active { "is-active" } else { "" };
+        let ws_status = if self._ws_subscription.is_some() {
+            let color = match self.ws_status {
+                WsStatus::Online => "is-success",
+                WsStatus::Reconnecting => "is-warning",
+                WsStatus::Offline => "is-danger",
+            };
+            html! {
+                <div class="navbar-item">
+                    <span class={classes!("tag", "is-light", color)} title="chat connection">{self.ws_status.as_str()}</span>
+                </div>
+            }
+        } else {
+            html! {}
+        };
         html! {
             <div class="header-container">
                 <nav class="navbar is-light" role="navigation" aria-label="main navigation">
                     <div class="navbar-brand">
-                        <a class="navbar-item" href="/">
+                        <Link<Route> classes={classes!("navbar-item")} to={Route::Welcome}>
                             <img alt="fuck you" src="/static/img/logo.png" width="100" height="100"/>
-                        </a>
+                        </Link<Route>>
 
                         <a href={String::from("javascript:void(0)")} role="button" onclick={ctx.link().callback(|_|HeaderMsg::ToggleNavbarActive)} class={format!{"navbar-burger {navbar_active}"}} aria-label="menu" aria-expanded="false" data-target="navbarBasicExample">
                         <span aria-hidden="true"></span>
@@ -78,6 +122,7 @@ impl Component for Header {
                     <div id="navbarBasicExample" class={format!("navbar-menu {navbar_active}")}>
                         <Navbar selected_navbar_name={ctx.props().selected_navbar_name.clone()} selected_navbar_parent_name={ctx.props().selected_navbar_parent_name.clone()}/>
                         <div class="navbar-end">
+                            {ws_status}
                             <div class="navbar-item has-dropdown is-hoverable">
                                 <a href={String::from("javascript:void(0)")} class="navbar-link" style="color:#000000">
                                     { user.name.unwrap_or("unnamed".to_string())}
@@ -91,6 +136,9 @@ impl Component for Header {
                                     {user.email}
                                 </a>
                                 <hr class="navbar-divider"/>
+                                <Link<RouteBody> classes={classes!("navbar-item")} to={RouteBody::ChangePwd}>
+                                    {"Change password"}
+                                </Link<RouteBody>>
                                 <a href={String::from("javascript:void(0)")} onclick={ctx.link().callback(|_|HeaderMsg::Logout)} class="navbar-item">
                                     {"Logout"}
                                 </a>
@@ -1,8 +1,12 @@
 use crate::component::menu::{Menu, MenuLabel};
 use crate::layout::header::Header;
-use crate::util::common;
+use crate::layout::request_status::RequestStatus;
+use crate::layout::session_guard::SessionGuard;
+use crate::util::request::InFlight;
+use crate::{Route, RouteBody};
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
+use yew_router::prelude::*;
 
 #[derive(PartialEq, Properties)]
 pub struct BodyProps {
@@ -12,8 +16,33 @@ pub struct BodyProps {
     pub menus: Vec<MenuLabel>,
 }
 
+/// a page reachable from the navbar or the left menu
+#[derive(Clone, PartialEq, Debug)]
+pub enum NavRoute {
+    App(Route),
+    Body(RouteBody),
+}
+
+impl NavRoute {
+    pub fn push(&self, navigator: &Navigator) {
+        match self {
+            NavRoute::App(route) => navigator.push(route),
+            NavRoute::Body(route) => navigator.push(route),
+        }
+    }
+
+    /// the page currently rendered, body routes win over the `/main/*` wildcard
+    pub fn current(route: Option<Route>, body: Option<RouteBody>) -> Option<Self> {
+        match (route, body) {
+            (Some(Route::Body), Some(body)) => Some(NavRoute::Body(body)),
+            (Some(route), _) => Some(NavRoute::App(route)),
+            _ => None,
+        }
+    }
+}
+
 pub struct Item<'a> {
-    pub path: &'a str,
+    pub route: NavRoute,
     pub navbar_name: Option<&'a str>,
     pub navbar_parent_name: Option<&'a str>,
     pub left_menu_name: Option<&'a str>,
@@ -22,19 +51,19 @@ pub struct Item<'a> {
 pub fn gen_items<'a>() -> Vec<Item<'a>> {
     vec![
         Item {
-            path: "/main/user",
+            route: NavRoute::Body(RouteBody::User),
             navbar_name: Some("User"),
             navbar_parent_name: Some("Modules"),
             left_menu_name: Some("User"),
         },
         Item {
-            path: "/main/role",
+            route: NavRoute::Body(RouteBody::Role),
             navbar_name: Some("User"),
             navbar_parent_name: Some("Modules"),
             left_menu_name: Some("Role"),
         },
         Item {
-            path: "/",
+            route: NavRoute::App(Route::Welcome),
             navbar_name: Some("Welcome"),
             navbar_parent_name: Some("Modules"),
             left_menu_name: None,
@@ -42,11 +71,12 @@ pub fn gen_items<'a>() -> Vec<Item<'a>> {
     ]
 }
 
-pub fn get_selected_navbar_and_menu() -> (Option<String>, Option<String>, Option<String>) {
+pub fn get_selected_navbar_and_menu(
+    current: Option<NavRoute>,
+) -> (Option<String>, Option<String>, Option<String>) {
     let mut res = (None, None, None);
-    let path = web_sys::window().unwrap().location().pathname().unwrap();
     let items = gen_items();
-    let items = items.iter().find(|x| x.path == &path);
+    let items = items.iter().find(|x| Some(&x.route) == current.as_ref());
     if let Some(v) = items {
         res.0 = v.navbar_name.map(|x| x.to_string());
         res.1 = v.navbar_parent_name.map(|x| x.to_string());
@@ -58,20 +88,30 @@ pub fn get_selected_navbar_and_menu() -> (Option<String>, Option<String>, Option
 
 #[function_component(Layout)]
 pub fn body(props: &BodyProps) -> Html {
+    let current = NavRoute::current(use_route::<Route>(), use_route::<RouteBody>());
     let (selected_navbar_name, selected_navbar_parent_name, selected_name) =
-        get_selected_navbar_and_menu();
+        get_selected_navbar_and_menu(current);
     let labels = props.menus.clone();
+    let navigator = use_navigator();
+    let in_flight = use_context::<InFlight>().unwrap_or_default();
     let on_select_menu = Callback::from(move |name: String| {
-        if let Some(item) = gen_items()
-            .iter()
-            .find(|x| x.left_menu_name.is_some_and(|x| x == &name))
-        {
-            common::redirect(item.path);
+        if let (Some(item), Some(navigator)) = (
+            gen_items()
+                .iter()
+                .find(|x| x.left_menu_name.is_some_and(|x| x == &name)),
+            &navigator,
+        ) {
+            item.route.push(navigator);
         }
     });
     html! {
         <>
+             if in_flight.count > 0 {
+                <progress class="progress is-small is-primary top-progress" max="100"></progress>
+             }
              <Header selected_navbar_name={selected_navbar_name} selected_navbar_parent_name={selected_navbar_parent_name} />
+             <SessionGuard />
+             <RequestStatus />
              {
                 if !props.menus.is_empty() {
                     html!{
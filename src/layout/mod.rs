@@ -1,3 +1,5 @@
 pub mod header;
 pub mod navbar;
 pub mod layout;
+pub mod request_status;
+pub mod session_guard;
@@ -1,9 +1,11 @@
-use crate::util::common;
-use serde::{Deserialize, Serialize};
+use crate::layout::layout::NavRoute;
+use crate::util::permission;
+use crate::{Route, RouteBody};
 use yew::prelude::*;
+use yew_router::prelude::*;
 
 pub enum NavbarMsg {
-    OnSelect(String),
+    OnSelect(NavRoute),
 }
 
 pub struct Navbar {
@@ -18,22 +20,46 @@ pub struct NavbarProps {
     pub selected_navbar_parent_name: Option<String>,
 }
 
-#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct NavbarNode {
     name: String,
     children: Vec<NavbarNode>,
-    path: Option<String>,
+    route: Option<NavRoute>,
     divider: bool,
+    permission: Option<String>,
+}
+
+impl NavbarNode {
+    // drop nodes the current user can not access, and parents left without children
+    fn visible(self) -> Option<Self> {
+        if !permission::has_opt(self.permission.as_deref()) {
+            return None;
+        }
+        if self.children.is_empty() {
+            return Some(self);
+        }
+        let children = self
+            .children
+            .into_iter()
+            .filter_map(|x| x.visible())
+            .collect::<Vec<NavbarNode>>();
+        if children.is_empty() {
+            return None;
+        }
+        Some(Self { children, ..self })
+    }
 }
 
 impl Component for Navbar {
     type Message = NavbarMsg;
     type Properties = NavbarProps;
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            NavbarMsg::OnSelect(path) => {
-                common::redirect(&path);
+            NavbarMsg::OnSelect(route) => {
+                if let Some(navigator) = ctx.link().navigator() {
+                    route.push(&navigator);
+                }
                 true
             }
         }
@@ -42,23 +68,29 @@ impl Component for Navbar {
     fn create(_ctx: &Context<Self>) -> Self {
         let data = vec![NavbarNode {
             name: "Modules".to_string(),
-            path: None,
+            route: None,
             divider: false,
+            permission: None,
             children: vec![
                 NavbarNode {
                     name: "Welcome".to_string(),
-                    path: Some("/".to_string()),
+                    route: Some(NavRoute::App(Route::Welcome)),
                     divider: true,
+                    permission: None,
                     children: vec![],
                 },
                 NavbarNode {
                     name: "User".to_string(),
-                    path: Some("/main/user".to_string()),
+                    route: Some(NavRoute::Body(RouteBody::User)),
                     divider: false,
+                    permission: Some(permission::USER_READ.to_string()),
                     children: vec![],
                 },
             ],
-        }];
+        }]
+        .into_iter()
+        .filter_map(|x| x.visible())
+        .collect();
 
         Self { data }
     }
@@ -72,10 +104,10 @@ impl Component for Navbar {
                 self.data
                 .iter()
                 .map(|item| {
-                    let path = item.path.clone();
+                    let route = item.route.clone();
                     if item.children.is_empty() {
                         html! {
-                            <a href={String::from("javascript:void(0)")} onclick = {ctx.link().callback(move|_|NavbarMsg::OnSelect(path.clone().unwrap()))} class={if selected_navbar_name.is_some() && &item.name == &selected_navbar_name.clone().unwrap()  {"navbar-item is-active"} else {"navbar-item"}}>
+                            <a href={String::from("javascript:void(0)")} onclick = {ctx.link().callback(move|_|NavbarMsg::OnSelect(route.clone().unwrap()))} class={if selected_navbar_name.is_some() && &item.name == &selected_navbar_name.clone().unwrap()  {"navbar-item is-active"} else {"navbar-item"}}>
                                 {item.name.clone()}
                             </a>
                         }
@@ -89,10 +121,10 @@ impl Component for Navbar {
                                     {
                                         item.children.iter().map(|child_item|{
                                             let class = {if selected_navbar_name.is_some() && &child_item.name == &selected_navbar_name.clone().unwrap() {"navbar-item is-active"} else {"navbar-item"}};
-                                            let path = child_item.path.clone();
+                                            let route = child_item.route.clone();
                                             html!{
                                                 <>
-                                                <a href={String::from("javascript:void(0)")} onclick = {ctx.link().callback(move|_|NavbarMsg::OnSelect(path.clone().unwrap()))} class={class} >
+                                                <a href={String::from("javascript:void(0)")} onclick = {ctx.link().callback(move|_|NavbarMsg::OnSelect(route.clone().unwrap()))} class={class} >
                                                     {child_item.name.clone()}
                                                 </a>
                                                 {
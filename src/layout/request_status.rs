@@ -0,0 +1,57 @@
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use crate::util::request::{self, RetryNotice};
+use std::sync::{Arc, Mutex};
+use yew::prelude::*;
+
+/// Shows what the request layer is doing in the background, retries for now.
+pub struct RequestStatus {
+    listener: Callback<RetryNotice>,
+    messages: Arc<Mutex<MessageListValue>>,
+}
+
+pub enum RequestStatusMsg {
+    Retry(RetryNotice),
+}
+
+impl Component for RequestStatus {
+    type Message = RequestStatusMsg;
+
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let listener = ctx.link().callback(RequestStatusMsg::Retry);
+        request::set_retry_listener(Some(listener.clone()));
+        Self {
+            listener,
+            messages: Default::default(),
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            RequestStatusMsg::Retry(notice) => {
+                self.messages.info(&format!(
+                    "{}, retrying ({}/{}) in {:.1}s",
+                    notice.reason,
+                    notice.attempt,
+                    notice.max_attempts,
+                    notice.delay_millis as f64 / 1000.0
+                ));
+                true
+            }
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        // the next layout may have registered already
+        if request::is_retry_listener(&self.listener) {
+            request::set_retry_listener(None);
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        html! {
+            <MessageList value = {self.messages.clone()}/>
+        }
+    }
+}
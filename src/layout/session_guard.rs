@@ -0,0 +1,78 @@
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use crate::util::session::{self, SessionEvent, SessionManager};
+use std::sync::{Arc, Mutex};
+use yew::prelude::*;
+
+/// Keeps the logged in session alive, or warns before it expires and logs out when it does.
+pub struct SessionGuard {
+    session: SessionManager,
+    messages: Arc<Mutex<MessageListValue>>,
+}
+
+pub enum SessionGuardMsg {
+    Session(SessionEvent),
+    Relogin,
+}
+
+impl SessionGuard {
+    fn warn_expiring(&self, ctx: &Context<Self>, minutes: i64) {
+        self.messages.prompt(
+            &format!("your session expires in {} minute(s)", minutes),
+            "Log in again",
+            ctx.link().callback(|_| SessionGuardMsg::Relogin),
+        );
+    }
+}
+
+impl Component for SessionGuard {
+    type Message = SessionGuardMsg;
+
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut session = SessionManager::default();
+        session.schedule(ctx.link().callback(SessionGuardMsg::Session));
+        Self {
+            session,
+            messages: Default::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SessionGuardMsg::Session(SessionEvent::Expiring(minutes)) => {
+                self.warn_expiring(ctx, minutes);
+                true
+            }
+            SessionGuardMsg::Session(SessionEvent::Refreshed | SessionEvent::Reschedule) => {
+                self.session
+                    .schedule(ctx.link().callback(SessionGuardMsg::Session));
+                false
+            }
+            SessionGuardMsg::Session(SessionEvent::RefreshFailed(err)) => {
+                // the expiry logout is still scheduled, let the user decide before it
+                log::warn!("refresh session error: {}", err);
+                self.warn_expiring(ctx, session::minutes_left());
+                true
+            }
+            SessionGuardMsg::Session(SessionEvent::Expired) => {
+                session::logout();
+                false
+            }
+            SessionGuardMsg::Relogin => {
+                session::logout();
+                false
+            }
+        }
+    }
+
+    fn destroy(&mut self, _ctx: &Context<Self>) {
+        self.session.cancel();
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        html! {
+            <MessageList value = {self.messages.clone()}/>
+        }
+    }
+}
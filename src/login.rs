@@ -1,10 +1,21 @@
+use crate::component::in_flight::InFlightState;
 use crate::util::common;
 use crate::util::common::CurrentUser;
+use crate::util::config;
+use crate::util::debounce::{Debounce, Generation};
+use crate::util::error::ErrorKind;
+use crate::util::permission;
+use crate::util::request::{self, InFlight};
+use crate::{Route, RouteBody};
 use serde_json;
-use user_cli::apis::{user_controller_api, Error};
+use user_cli::apis::user_controller_api;
 use user_cli::models;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
+use yew_router::prelude::*;
+
+// in-flight key of the calls behind the login button
+const SUBMIT_KEY: &str = "login";
 
 #[derive(Debug)]
 enum ValidStatus {
@@ -17,9 +28,15 @@ pub struct Login {
     refs: Vec<NodeRef>,
     email: String,
     pwd: String,
+    remember_me: bool,
+    // validated destination from `?next=`
+    next: Option<String>,
     email_valid: ValidStatus,
     pwd_valid: ValidStatus,
     request_fail_msg: String,
+    email_debounce: Debounce,
+    email_generation: Generation,
+    in_flight: InFlightState,
 }
 
 pub enum ValidateExistEmailOperation {
@@ -29,14 +46,16 @@ pub enum ValidateExistEmailOperation {
 
 pub enum LoginMsg {
     ValidateExistEmail(ValidateExistEmailOperation),
-    ValidateExistEmailSuccess(ValidateExistEmailOperation),
-    ValidateExistEmailFail(String),
+    ValidateExistEmailSuccess(u64, ValidateExistEmailOperation),
+    ValidateExistEmailFail(u64, String),
     PasswordChange(web_sys::KeyboardEvent),
     EmailChange(web_sys::KeyboardEvent),
     HandleLoginSuccess { token: String },
     HandleCheckTokenSuccess(common::CurrentUser),
     HandleLoginFail(String),
     KeyDownLogin(web_sys::KeyboardEvent),
+    ToggleRememberMe,
+    InFlightChange(InFlight),
 }
 
 impl Component for Login {
@@ -44,14 +63,26 @@ impl Component for Login {
 
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let next = ctx
+            .link()
+            .location()
+            .and_then(|x| x.query::<common::NextQuery>().ok())
+            .and_then(|x| x.next)
+            .and_then(|x| common::validate_next(&x));
+        let in_flight = InFlightState::subscribe(ctx, LoginMsg::InFlightChange);
         Self {
             refs: vec![NodeRef::default()],
-            email: common::get_local_storage("email").unwrap_or_default(),
-            pwd: common::get_local_storage("pwd").unwrap_or_default(),
+            email: common::get_session_item("email").unwrap_or_default(),
+            pwd: Default::default(),
+            remember_me: common::is_remember_me(),
+            next,
             email_valid: ValidStatus::None,
             pwd_valid: ValidStatus::None,
             request_fail_msg: Default::default(),
+            email_debounce: Default::default(),
+            email_generation: Default::default(),
+            in_flight,
         }
     }
 
@@ -69,39 +100,42 @@ impl Component for Login {
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             LoginMsg::ValidateExistEmail(operation) => {
+                if let ValidateExistEmailOperation::Login = operation {
+                    if self.in_flight.is_pending(SUBMIT_KEY) {
+                        return false;
+                    }
+                }
+                self.email_debounce.cancel();
+                let generation = self.email_generation.next();
                 let email = self.email.clone();
                 ctx.link().send_future(async move {
-                    match user_controller_api::validate_exist_email(
-                        &common::get_cli_config_without_token().unwrap(),
-                        &email,
-                    )
-                    .await
-                    {
-                        Ok(_) => LoginMsg::ValidateExistEmailSuccess(operation),
+                    let config = match common::get_cli_config_without_token() {
+                        Ok(v) => v,
                         Err(err) => {
-                            if let Error::ResponseError(ref err) = err {
-                                if let Some(ref err) = err.entity {
-                                    if let user_controller_api::ValidateExistEmailError::Status400(
-                                        res,
-                                    ) = err
-                                    {
-                                        return LoginMsg::ValidateExistEmailFail(res.msg.clone());
-                                    }
-                                    if let user_controller_api::ValidateExistEmailError::Status500(
-                                        res,
-                                    ) = err
-                                    {
-                                        return LoginMsg::ValidateExistEmailFail(res.msg.clone());
-                                    }
-                                }
-                            }
-                            LoginMsg::ValidateExistEmailFail(err.to_string())
+                            return LoginMsg::ValidateExistEmailFail(generation, err.to_string())
                         }
+                    };
+                    let call = request::cli_anonymous(
+                        "validate_exist_email",
+                        user_controller_api::validate_exist_email(&config, &email),
+                    );
+                    let res = match &operation {
+                        ValidateExistEmailOperation::Login => {
+                            request::track_as(SUBMIT_KEY, call).await
+                        }
+                        ValidateExistEmailOperation::Nothing => call.await,
+                    };
+                    match res {
+                        Ok(_) => LoginMsg::ValidateExistEmailSuccess(generation, operation),
+                        Err(err) => LoginMsg::ValidateExistEmailFail(generation, err.to_string()),
                     }
                 });
                 false
             }
-            LoginMsg::ValidateExistEmailSuccess(operation) => {
+            LoginMsg::ValidateExistEmailSuccess(generation, operation) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::Valid;
                 match operation {
                     ValidateExistEmailOperation::Nothing => (),
@@ -116,48 +150,48 @@ impl Component for Login {
                             pwd: self.pwd.clone(),
                         };
                         ctx.link().send_future(async move {
-                            match user_controller_api::login(
-                                &common::get_cli_config_without_token().unwrap(),
-                                req,
+                            let config = match common::get_cli_config_without_token() {
+                                Ok(v) => v,
+                                Err(err) => return LoginMsg::HandleLoginFail(err.to_string()),
+                            };
+                            match request::track_as(
+                                SUBMIT_KEY,
+                                request::cli_anonymous(
+                                    "login",
+                                    user_controller_api::login(&config, req),
+                                ),
                             )
                             .await
                             {
                                 Ok(res) => LoginMsg::HandleLoginSuccess {
                                     token: res.data.clone(),
                                 },
-                                Err(err) => {
-                                    if let Error::ResponseError(ref err) = err {
-                                        if let Some(ref err) = err.entity {
-                                            if let user_controller_api::LoginError::Status400(res) =
-                                                err
-                                            {
-                                                return LoginMsg::HandleLoginFail(res.msg.clone());
-                                            }
-                                            if let user_controller_api::LoginError::Status500(res) =
-                                                err
-                                            {
-                                                return LoginMsg::HandleLoginFail(res.msg.clone());
-                                            }
-                                        }
-                                    }
-                                    LoginMsg::HandleLoginFail(err.to_string())
-                                }
+                                Err(err) => LoginMsg::HandleLoginFail(err.to_string()),
                             }
                         });
                     }
                 }
                 true
             }
-            LoginMsg::ValidateExistEmailFail(e) => {
+            LoginMsg::ValidateExistEmailFail(generation, e) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::InValid(format!("{}", e));
                 true
             }
             LoginMsg::EmailChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                if el.value() == self.email {
+                    return false;
+                }
                 self.email = el.value();
-                ctx.link().send_message(LoginMsg::ValidateExistEmail(
-                    ValidateExistEmailOperation::Nothing,
-                ));
+                let link = ctx.link().clone();
+                self.email_debounce.call(move || {
+                    link.send_message(LoginMsg::ValidateExistEmail(
+                        ValidateExistEmailOperation::Nothing,
+                    ))
+                });
                 true
             }
             LoginMsg::PasswordChange(e) => {
@@ -170,12 +204,26 @@ impl Component for Login {
                 true
             }
             LoginMsg::HandleLoginSuccess { token } => {
-                common::set_local_storage("token", &token);
+                common::set_remember_me(self.remember_me);
+                common::set_token(&token);
                 ctx.link().send_future(async move {
-                    match user_controller_api::get_current_user(&common::get_cli_config().unwrap())
-                        .await
-                    {
-                        Ok(res) => LoginMsg::HandleCheckTokenSuccess({
+                    let config = match common::get_cli_config() {
+                        Ok(v) => v,
+                        Err(err) => return LoginMsg::HandleLoginFail(err.to_string()),
+                    };
+                    // one key for both calls, the button stays disabled until the user is stored
+                    let res = request::track_as(SUBMIT_KEY, async move {
+                        let res = request::cli(
+                            "get_current_user",
+                            user_controller_api::get_current_user(&config),
+                        )
+                        .await?;
+                        let permissions = permission::fetch().await?;
+                        Ok::<_, ErrorKind>((res, permissions))
+                    })
+                    .await;
+                    match res {
+                        Ok((res, permissions)) => LoginMsg::HandleCheckTokenSuccess({
                             let a = res.data.clone();
                             CurrentUser {
                                 id: a.id,
@@ -187,47 +235,41 @@ impl Component for Login {
                                 created_at: a.created_at,
                                 updated_at: a.updated_at,
                                 expire_at: a.expire_at,
+                                permissions,
                             }
                         }),
-                        Err(err) => {
-                            if let Error::ResponseError(ref err) = err {
-                                if let Some(ref err) = err.entity {
-                                    if let user_controller_api::GetCurrentUserError::Status400(
-                                        res,
-                                    ) = err
-                                    {
-                                        return LoginMsg::HandleLoginFail(res.msg.clone());
-                                    }
-                                    if let user_controller_api::GetCurrentUserError::Status401(
-                                        res,
-                                    ) = err
-                                    {
-                                        return LoginMsg::HandleLoginFail(res.msg.clone());
-                                    }
-                                    if let user_controller_api::GetCurrentUserError::Status500(
-                                        res,
-                                    ) = err
-                                    {
-                                        return LoginMsg::HandleLoginFail(res.msg.clone());
-                                    }
-                                }
-                            }
-                            LoginMsg::HandleLoginFail(err.to_string())
-                        }
+                        Err(err) => LoginMsg::HandleLoginFail(err.to_string()),
                     }
                 });
                 false
             }
             LoginMsg::HandleCheckTokenSuccess(v) => {
-                common::set_local_storage(
-                    "current_user",
-                    serde_json::to_string(&v).unwrap().as_str(),
-                );
-                common::set_local_storage("email", self.email.as_str());
-                common::set_local_storage("pwd", self.pwd.as_str());
-                common::redirect("/main/user");
+                match serde_json::to_string(&v) {
+                    Ok(v) => common::set_session_item("current_user", &v),
+                    Err(err) => {
+                        ctx.link()
+                            .send_message(LoginMsg::HandleLoginFail(err.to_string()));
+                        return false;
+                    }
+                }
+                // same storage as register and forget password, cleared from both without "remember me"
+                if self.remember_me {
+                    common::set_session_item("email", self.email.as_str());
+                } else {
+                    common::del_session_item("email");
+                }
+                match &self.next {
+                    // validated path with its query, not a typed route
+                    Some(next) => common::redirect(next),
+                    None => {
+                        if let Some(navigator) = ctx.link().navigator() {
+                            navigator.push(&RouteBody::User);
+                        }
+                    }
+                }
                 false
             }
+            LoginMsg::InFlightChange(v) => self.in_flight.update(v, SUBMIT_KEY),
             LoginMsg::HandleLoginFail(msg) => {
                 self.request_fail_msg = format!("login failed: {}", msg);
                 true
@@ -240,6 +282,10 @@ impl Component for Login {
                 }
                 false
             }
+            LoginMsg::ToggleRememberMe => {
+                self.remember_me = !self.remember_me;
+                true
+            }
         }
     }
 
@@ -262,6 +308,7 @@ impl Component for Login {
             }
             ValidStatus::None => "input is-info is-medium is-rounded",
         };
+        let submitting = self.in_flight.is_pending(SUBMIT_KEY);
         html! {
             <>
             <header>
@@ -297,22 +344,30 @@ impl Component for Login {
                             {&self.request_fail_msg}
                         </p>
                     </div>
+                    <div class="field has-text-left">
+                        <label class="checkbox">
+                        <input type="checkbox" checked={self.remember_me} onchange={ctx.link().callback(|_|LoginMsg::ToggleRememberMe)}/>
+                        {" Remember me"}
+                        </label>
+                    </div>
                     <br />
-                    <button class="button is-block is-fullwidth is-primary is-medium is-rounded" onclick={ctx.link().callback(|_|LoginMsg::ValidateExistEmail(ValidateExistEmailOperation::Login))}>
+                    <button class={classes!("button", "is-block", "is-fullwidth", "is-primary", "is-medium", "is-rounded", submitting.then_some("is-loading"))} disabled={submitting} onclick={ctx.link().callback(|_|LoginMsg::ValidateExistEmail(ValidateExistEmailOperation::Login))}>
                         {"Login"}
                     </button>
                     <br/>
                     <nav class="level">
                     <div class="level-item has-text-centered">
                         <div>
-                        <a href="/forget_pwd">{"Forgot Password?"}</a>
+                        <Link<Route> to={Route::ForgetPwd}>{"Forgot Password?"}</Link<Route>>
                         </div>
                     </div>
-                    <div class="level-item has-text-centered">
-                        <div>
-                        <a href="/register">{"Create an Account"}</a>
+                    if config::get().features.register {
+                        <div class="level-item has-text-centered">
+                            <div>
+                            <Link<Route> to={Route::Register}>{"Create an Account"}</Link<Route>>
+                            </div>
                         </div>
-                    </div>
+                    }
                     </nav>
                 </div>
                 </div>
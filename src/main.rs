@@ -1,5 +1,6 @@
 #![feature(is_some_and)]
 #![feature(linked_list_remove)]
+mod change_pwd;
 mod component;
 mod confirm_form;
 mod error_page;
@@ -7,39 +8,65 @@ mod forget_pwd;
 mod layout;
 mod login;
 mod register;
+mod role_form;
 mod role_list;
+mod role_list_item;
+mod user_export;
+mod user_filter;
 mod user_form;
 mod user_list;
 mod user_list_item;
+mod user_role_form;
+mod user_search;
 mod util;
 
+use change_pwd::ChangePwd;
+use component::in_flight::InFlightProvider;
 use component::menu::{MenuLabel, MenuNode};
 use component::welcome::Welcome;
-use error_page::{page_not_found::PageNotFound, request_error::RequestError};
+use error_page::{
+    error_boundary::{self, ErrorBoundary},
+    page_not_found::PageNotFound,
+    request_error::RequestError,
+};
 use forget_pwd::ForgetPwd;
-use layout::layout::Layout;
+use layout::layout::{gen_items, Layout, NavRoute};
 use login::Login;
 use register::Register;
 use role_list::RoleList;
 use user_list::UserList;
+use util::{common, config, interceptor, permission};
+use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 use yew::virtual_dom::VNode;
 use yew_router::prelude::*;
 use yew_router::BrowserRouter;
 
-#[derive(Clone, Routable, PartialEq)]
-enum RouteBody {
+#[derive(Clone, Routable, PartialEq, Debug)]
+pub enum RouteBody {
     #[not_found]
     #[at("/page_not_found")]
     PageNotFound,
     #[at("/main/user")]
-    User, // Hello,
+    User,
     #[at("/main/role")]
-    Role, // Hello,
+    Role,
+    #[at("/main/change_pwd")]
+    ChangePwd,
 }
 
-#[derive(Clone, Routable, PartialEq)]
-enum Route {
+impl RouteBody {
+    fn permission(&self) -> Option<&'static str> {
+        match self {
+            RouteBody::PageNotFound | RouteBody::ChangePwd => None,
+            RouteBody::User => Some(permission::USER_READ),
+            RouteBody::Role => Some(permission::ROLE_READ),
+        }
+    }
+}
+
+#[derive(Clone, Routable, PartialEq, Debug)]
+pub enum Route {
     #[not_found]
     #[at("/page_not_found")]
     PageNotFound,
@@ -55,11 +82,18 @@ enum Route {
     Body,
     #[at("/401")]
     Unauthorized,
+    #[at("/403")]
+    Forbidden,
     #[at("/404")]
     NotFound,
 }
 
 fn switch_body(route: RouteBody) -> VNode {
+    if !permission::has_opt(route.permission()) {
+        return html! {
+            <RequestError status={403} />
+        };
+    }
     match route {
         RouteBody::PageNotFound => {
             html! {
@@ -77,6 +111,12 @@ fn switch_body(route: RouteBody) -> VNode {
                 <RoleList />
             }
         }
+
+        RouteBody::ChangePwd => {
+            html! {
+                <ChangePwd />
+            }
+        }
     }
 }
 
@@ -88,6 +128,11 @@ fn switch(route: Route) -> Html {
             }
         }
         Route::Register => {
+            if !config::get().features.register {
+                return html! {
+                    <PageNotFound />
+                };
+            }
             html! {
                 <Register />
             }
@@ -103,19 +148,29 @@ fn switch(route: Route) -> Html {
             }
         }
         Route::Body => {
-            let menus = vec![MenuLabel {
-                label: Some(String::from("User Management")),
-                nodes: vec![
-                    MenuNode {
-                        name: String::from("User"),
-                        children: Default::default(),
-                    },
-                    MenuNode {
-                        name: String::from("Role"),
-                        children: Default::default(),
-                    },
-                ],
-            }];
+            let nodes = gen_items()
+                .into_iter()
+                .filter_map(|item| match (item.left_menu_name, item.route) {
+                    (Some(name), NavRoute::Body(route))
+                        if permission::has_opt(route.permission()) =>
+                    {
+                        Some(MenuNode {
+                            name: String::from(name),
+                            children: Default::default(),
+                            tag: None,
+                        })
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<MenuNode>>();
+            let menus = if nodes.is_empty() {
+                vec![]
+            } else {
+                vec![MenuLabel {
+                    label: Some(String::from("User Management")),
+                    nodes,
+                }]
+            };
             html! {
                 <Layout menus = {menus} content={html!{<Switch<RouteBody> render={switch_body} />}}/>
             }
@@ -126,6 +181,12 @@ fn switch(route: Route) -> Html {
             }
         }
 
+        Route::Forbidden => {
+            html! {
+                <RequestError status={403} />
+            }
+        }
+
         Route::NotFound => {
             html! {
                 <RequestError status={404} />
@@ -143,13 +204,24 @@ fn switch(route: Route) -> Html {
 #[function_component(Main)]
 fn app() -> Html {
     html! {
-        <BrowserRouter>
-            <Switch<Route> render={switch} />
-        </BrowserRouter>
+        <ErrorBoundary>
+            <InFlightProvider>
+                <BrowserRouter>
+                    <Switch<Route> render={switch} />
+                </BrowserRouter>
+            </InFlightProvider>
+        </ErrorBoundary>
     }
 }
 
 fn main() {
     wasm_logger::init(wasm_logger::Config::default());
-    yew::Renderer::<Main>::new().render();
+    error_boundary::install_panic_hook();
+    common::migrate_storage();
+    interceptor::install();
+    spawn_local(async {
+        config::load().await;
+        permission::backfill().await;
+        yew::Renderer::<Main>::new().render();
+    });
 }
@@ -1,10 +1,18 @@
+use crate::component::in_flight::InFlightState;
 use crate::util::common;
+use crate::util::debounce::{Debounce, Generation};
+use crate::util::error::ErrorKind;
+use crate::util::request::{self, InFlight};
+use crate::Route;
 use gloo::timers::callback::Timeout;
 use user_cli::apis::user_controller_api;
 use user_cli::models;
 use web_sys::HtmlInputElement;
 use yew::prelude::*;
+use yew_router::prelude::*;
 
+// in-flight key of the calls behind the register button
+const SUBMIT_KEY: &str = "register";
 const DEFAULT_CODE_BUTTON_TEXT: &str = "Generate Code";
 const DEFAULT_CODE_BUTTON_CLASS: &str =
     "button is-block is-fullwidth is-primary is-medium is-rounded";
@@ -32,6 +40,9 @@ pub struct Register {
     code_button_class: String,
     password_confirm: String,
     code_fail_msg: String,
+    email_debounce: Debounce,
+    email_generation: Generation,
+    in_flight: InFlightState,
 }
 
 pub enum ValidateNotExistEmailOperation {
@@ -42,8 +53,8 @@ pub enum ValidateNotExistEmailOperation {
 
 pub enum RegisterMsg {
     ValidateNotExistEmail(ValidateNotExistEmailOperation),
-    ValidateNotExistEmailSuccess(ValidateNotExistEmailOperation),
-    ValidateNotExistEmailFail(Box<dyn std::error::Error>),
+    ValidateNotExistEmailSuccess(u64, ValidateNotExistEmailOperation),
+    ValidateNotExistEmailFail(u64, Box<dyn std::error::Error>),
     EmailChange(web_sys::KeyboardEvent),
     PasswordChange(web_sys::KeyboardEvent),
     PasswordConfirmChange(web_sys::KeyboardEvent),
@@ -54,6 +65,7 @@ pub enum RegisterMsg {
     HandleSendEmailCodeSuccess(usize),
     HandleSendEmailCodeError(Box<dyn std::error::Error>),
     HandleSendEmailCodeHint(Box<dyn std::error::Error>),
+    InFlightChange(InFlight),
 }
 
 impl Component for Register {
@@ -72,7 +84,8 @@ impl Component for Register {
         }
     }
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let in_flight = InFlightState::subscribe(ctx, RegisterMsg::InFlightChange);
         Self {
             refs: vec![NodeRef::default()],
             email: Default::default(),
@@ -89,22 +102,44 @@ impl Component for Register {
             password_confirm: Default::default(),
             code_fail_msg: Default::default(),
             code_button_class: DEFAULT_CODE_BUTTON_CLASS.to_string(),
+            email_debounce: Default::default(),
+            email_generation: Default::default(),
+            in_flight,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             RegisterMsg::ValidateNotExistEmail(operation) => {
+                if let ValidateNotExistEmailOperation::Register = operation {
+                    if self.in_flight.is_pending(SUBMIT_KEY) {
+                        return false;
+                    }
+                }
+                self.email_debounce.cancel();
+                let generation = self.email_generation.next();
                 let email = self.email.clone();
                 ctx.link().send_future(async move {
-                    match common::validate_not_exist_email(&email).await {
-                        Ok(_) => RegisterMsg::ValidateNotExistEmailSuccess(operation),
-                        Err(err) => RegisterMsg::ValidateNotExistEmailFail(Box::new(err)),
+                    let call = common::validate_not_exist_email(&email);
+                    let res = match &operation {
+                        ValidateNotExistEmailOperation::Register => {
+                            request::track_as(SUBMIT_KEY, call).await
+                        }
+                        _ => call.await,
+                    };
+                    match res {
+                        Ok(_) => RegisterMsg::ValidateNotExistEmailSuccess(generation, operation),
+                        Err(err) => {
+                            RegisterMsg::ValidateNotExistEmailFail(generation, Box::new(err))
+                        }
                     }
                 });
                 false
             }
-            RegisterMsg::ValidateNotExistEmailSuccess(operation) => {
+            RegisterMsg::ValidateNotExistEmailSuccess(generation, operation) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::Valid;
                 self.code_button_disabled = false;
                 match operation {
@@ -135,9 +170,16 @@ impl Component for Register {
                             name: None,
                         };
                         ctx.link().send_future(async move {
-                            match user_controller_api::register(
-                                &common::get_cli_config_without_token().unwrap(),
-                                req,
+                            let config = match common::get_cli_config_without_token() {
+                                Ok(v) => v,
+                                Err(err) => return RegisterMsg::HandleRegisterError(Box::new(err)),
+                            };
+                            match request::track_as(
+                                SUBMIT_KEY,
+                                request::cli_anonymous(
+                                    "register",
+                                    user_controller_api::register(&config, req),
+                                ),
                             )
                             .await
                             {
@@ -155,43 +197,54 @@ impl Component for Register {
                             from: models::SendEmailCodeFrom::Register,
                         };
                         ctx.link().send_future(async move {
-                            let res = user_controller_api::send_email_code(
-                                &common::get_cli_config_without_token().unwrap(),
-                                req,
+                            let config = match common::get_cli_config_without_token() {
+                                Ok(v) => v,
+                                Err(err) => {
+                                    return RegisterMsg::HandleSendEmailCodeError(Box::new(err))
+                                }
+                            };
+                            let res = request::cli_anonymous(
+                                "send_email_code",
+                                user_controller_api::send_email_code(&config, req),
                             )
                             .await;
                             match res {
                                 Ok(res) => {
                                     RegisterMsg::HandleSendEmailCodeSuccess(res.data as usize)
                                 }
-                                Err(err) => match err {
-                                    user_cli::apis::Error::ResponseError(ref f) => {
-                                        if f.status.as_u16() == 452 {
-                                            // hint
-                                            RegisterMsg::HandleSendEmailCodeHint(Box::new(err))
-                                        } else {
-                                            RegisterMsg::HandleSendEmailCodeError(Box::new(err))
-                                        }
+                                Err(err) => {
+                                    if err.is_hint() {
+                                        RegisterMsg::HandleSendEmailCodeHint(Box::new(err))
+                                    } else {
+                                        RegisterMsg::HandleSendEmailCodeError(Box::new(err))
                                     }
-                                    _ => RegisterMsg::HandleSendEmailCodeError(Box::new(err)),
-                                },
+                                }
                             }
                         });
                     }
                 }
                 true
             }
-            RegisterMsg::ValidateNotExistEmailFail(e) => {
+            RegisterMsg::ValidateNotExistEmailFail(generation, e) => {
+                if !self.email_generation.is_current(generation) {
+                    return false;
+                }
                 self.email_valid = ValidStatus::InValid(format!("{}", e));
                 self.code_button_disabled = true;
                 true
             }
             RegisterMsg::EmailChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                if el.value() == self.email {
+                    return false;
+                }
                 self.email = el.value();
-                ctx.link().send_message(RegisterMsg::ValidateNotExistEmail(
-                    ValidateNotExistEmailOperation::Nothing,
-                ));
+                let link = ctx.link().clone();
+                self.email_debounce.call(move || {
+                    link.send_message(RegisterMsg::ValidateNotExistEmail(
+                        ValidateNotExistEmailOperation::Nothing,
+                    ))
+                });
                 true
             }
             RegisterMsg::CodeChange(e) => {
@@ -223,13 +276,28 @@ impl Component for Register {
                 true
             }
 
+            RegisterMsg::InFlightChange(v) => self.in_flight.update(v, SUBMIT_KEY),
             RegisterMsg::HandleRegisterSuccess => {
-                common::set_local_storage("email", self.email.as_str());
-                common::set_local_storage("pwd", self.pwd.as_str());
-                common::redirect("/login");
+                // prefills the login, kept across restarts only with "remember me"
+                common::set_session_item("email", self.email.as_str());
+                if let Some(navigator) = ctx.link().navigator() {
+                    navigator.push(&Route::Login);
+                }
                 false
             }
             RegisterMsg::HandleRegisterError(e) => {
+                // the backend may refuse a single input, show it next to that one
+                if let Some(err) = e.downcast_ref::<ErrorKind>() {
+                    for (name, valid) in [
+                        ("email", &mut self.email_valid),
+                        ("pwd", &mut self.pwd_valid),
+                        ("code", &mut self.code_valid),
+                    ] {
+                        if let Some(msg) = err.field(name) {
+                            *valid = ValidStatus::InValid(msg.to_string());
+                        }
+                    }
+                }
                 self.request_fail_msg = format!("{}", e);
                 true
             }
@@ -261,6 +329,9 @@ impl Component for Register {
             }
             RegisterMsg::HandleSendEmailCodeError(e) => {
                 self.code_button_class = DEFAULT_CODE_BUTTON_CLASS.to_string();
+                if let Some(msg) = e.downcast_ref::<ErrorKind>().and_then(|x| x.field("email")) {
+                    self.email_valid = ValidStatus::InValid(msg.to_string());
+                }
                 self.code_fail_msg = format!("{}", e);
                 true
             }
@@ -312,6 +383,7 @@ impl Component for Register {
             }
             ValidStatus::None => "input is-info is-medium is-rounded",
         };
+        let submitting = self.in_flight.is_pending(SUBMIT_KEY);
         html! {
             <>
             <header>
@@ -389,7 +461,7 @@ impl Component for Register {
                         <br/>
                         <div class="field">
                             <p class="control">
-                                <button class="button is-block is-fullwidth is-primary is-medium is-rounded" onclick={ctx.link().callback(|_|RegisterMsg::ValidateNotExistEmail(ValidateNotExistEmailOperation::Register))}>
+                                <button class={classes!("button", "is-block", "is-fullwidth", "is-primary", "is-medium", "is-rounded", submitting.then_some("is-loading"))} disabled={submitting} onclick={ctx.link().callback(|_|RegisterMsg::ValidateNotExistEmail(ValidateNotExistEmailOperation::Register))}>
                                 {"Register"}
                                 </button>
                             </p>
@@ -401,7 +473,7 @@ impl Component for Register {
                         <nav class="level">
                         <div class="level-item has-text-centered">
                             <div>
-                            <a href="/login">{"Return to login"}</a>
+                            <Link<Route> to={Route::Login}>{"Return to login"}</Link<Route>>
                             </div>
                         </div>
                         </nav>
@@ -0,0 +1,162 @@
+use crate::role_list::Role;
+use crate::util::request;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use std::sync::{Arc, Mutex};
+use yew::prelude::*;
+use yew::Properties;
+
+pub struct RoleForm {
+    messages: Arc<Mutex<MessageListValue>>,
+}
+
+pub enum RoleFormMsg {
+    Close,
+    NameChange(web_sys::Event),
+    DescriptionChange(web_sys::Event),
+    Save,
+    SaveSuccess,
+    SaveError(Box<dyn std::error::Error>),
+}
+
+#[derive(Serialize)]
+pub struct RoleCreateReq {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct RoleUpdateReq {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct RoleFormProps {
+    #[prop_or_default]
+    pub value: RefCell<Role>,
+    #[prop_or_default]
+    pub update: Callback<()>,
+    pub closed: Rc<RefCell<bool>>,
+}
+
+impl Component for RoleForm {
+    type Message = RoleFormMsg;
+
+    type Properties = RoleFormProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            messages: Default::default(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            RoleFormMsg::Close => {
+                *ctx.props().closed.borrow_mut() = true;
+                true
+            }
+            RoleFormMsg::NameChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                ctx.props().value.borrow_mut().name = el.value();
+                false
+            }
+            RoleFormMsg::DescriptionChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                ctx.props().value.borrow_mut().description = Some(el.value());
+                false
+            }
+            RoleFormMsg::Save => {
+                let role = ctx.props().value.borrow().clone();
+                if role.name.trim().is_empty() {
+                    self.messages.warn("please type in role name");
+                    return true;
+                }
+                ctx.link().send_future(async move {
+                    let res = if role.id == 0 {
+                        let req = RoleCreateReq {
+                            name: role.name,
+                            description: role.description,
+                        };
+                        request::post::<i64, _>(request::Host::ApiBase, "/role/create", &req)
+                            .await
+                            .map(|_| ())
+                    } else {
+                        let req = RoleUpdateReq {
+                            id: role.id,
+                            name: role.name,
+                            description: role.description,
+                        };
+                        request::put::<i64, _>(request::Host::ApiBase, "/role/update", &req)
+                            .await
+                            .map(|_| ())
+                    };
+                    match res {
+                        Ok(_) => RoleFormMsg::SaveSuccess,
+                        Err(err) => RoleFormMsg::SaveError(Box::new(err)),
+                    }
+                });
+                false
+            }
+            RoleFormMsg::SaveSuccess => {
+                ctx.props().update.emit(());
+                ctx.link().send_message(RoleFormMsg::Close);
+                false
+            }
+            RoleFormMsg::SaveError(_err) => {
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if ctx.props().closed.borrow().clone() {
+            return html! {};
+        }
+        let val = ctx.props().value.borrow();
+        let title = if val.id == 0 {
+            "Role Create"
+        } else {
+            "Role Edit"
+        };
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <MessageList value={self.messages.clone()}/>
+                    <header class="modal-card-head">
+                    <p class="modal-card-title">{title}</p>
+                    <button class="delete" aria-label="close" onclick={ctx.link().callback(|_|RoleFormMsg::Close)}></button>
+                    </header>
+                    <section class="modal-card-body">
+
+                    <div class="field">
+                        <label class="label">{"Name"}</label>
+                        <div class="control">
+                        <input class="input" value={val.name.clone()} type="text" placeholder="admin" onchange={ctx.link().callback(|e:web_sys::Event|RoleFormMsg::NameChange(e))}/>
+                        </div>
+                    </div>
+
+                    <div class="field">
+                        <label class="label">{"Description"}</label>
+                        <div class="control">
+                        <input class="input" value={val.description.clone()} type="text" placeholder="administrator" onchange={ctx.link().callback(|e:web_sys::Event|RoleFormMsg::DescriptionChange(e))}/>
+                        </div>
+                    </div>
+
+                    </section>
+                    <footer class="modal-card-foot">
+                    <button class="button is-success"  onclick={ctx.link().callback(|_|RoleFormMsg::Save)}>{"Save changes"}</button>
+                    <button class="button" onclick={ctx.link().callback(|_|RoleFormMsg::Close)} >{"Cancel"}</button>
+                    </footer>
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,38 +1,265 @@
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use crate::component::pager::{Page, Pager};
+use crate::confirm_form::ConfirmForm;
+use crate::role_form::RoleForm;
+use crate::role_list_item::RoleListItem;
+use crate::util::config;
+use crate::util::request;
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use yew::prelude::*;
+use yew::Properties;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
 
-pub struct RoleList;
+pub struct RoleList {
+    selected_row: Option<Role>,
+    form_value: Option<Role>,
+    role_form_closed: Rc<RefCell<bool>>,
+    confirm_form_closed: Rc<RefCell<bool>>,
+    messages: Arc<Mutex<MessageListValue>>,
+    loading: bool,
+    key_word: Option<String>,
+}
+
+pub enum RoleListMsg {
+    Refresh,
+    HandleSearchSuccess(Vec<Role>, usize),
+    HandleSearchFail(Box<dyn std::error::Error>),
+    PageChanged(Page),
+    OnSelect(Role),
+    Create,
+    Edit,
+    Delete,
+    DeleteConfirm,
+    HandleDeleteSuccess,
+    HandleDeleteFail(Box<dyn std::error::Error>),
+    KeywordChange(web_sys::KeyboardEvent),
+}
+
+#[derive(Serialize)]
+pub struct DeleteReq {
+    pub ids: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct RoleListProps {
+    #[prop_or_default]
+    pub data: RefCell<Vec<Role>>,
+
+    #[prop_or_default]
+    pub page: RefCell<Page>,
+}
 
 impl Component for RoleList {
-    type Message = ();
+    type Message = RoleListMsg;
 
-    type Properties = ();
+    type Properties = RoleListProps;
 
     fn create(_ctx: &Context<Self>) -> Self {
-        Self
+        Self {
+            selected_row: Default::default(),
+            form_value: Default::default(),
+            role_form_closed: Rc::new(RefCell::new(true)),
+            confirm_form_closed: Rc::new(RefCell::new(true)),
+            messages: Default::default(),
+            loading: Default::default(),
+            key_word: Default::default(),
+        }
     }
 
-    fn view(&self, _ctx: &Context<Self>) -> Html {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            RoleListMsg::Refresh => {
+                self.selected_row = None;
+                self.loading = true;
+
+                let key_word = self.key_word.clone().unwrap_or_default();
+                let page = ctx.props().page.borrow();
+                let index = page.index;
+                let size = page.size;
+
+                ctx.link().send_future(async move {
+                    let params = vec![
+                        ("key_word", key_word),
+                        ("index", index.to_string()),
+                        ("size", size.to_string()),
+                    ];
+                    match request::get::<Vec<Role>, _, _>(
+                        request::Host::ApiBase,
+                        "/role/search",
+                        Some(params),
+                    )
+                    .await
+                    {
+                        Ok(res) => RoleListMsg::HandleSearchSuccess(
+                            res.data.unwrap_or_default(),
+                            res.total.unwrap_or_default(),
+                        ),
+                        Err(err) => RoleListMsg::HandleSearchFail(Box::new(err)),
+                    }
+                });
+                false
+            }
+            RoleListMsg::HandleSearchFail(_err) => {
+                self.messages.request_failed(_err.as_ref());
+                self.loading = false;
+                true
+            }
+            RoleListMsg::HandleSearchSuccess(v, total) => {
+                *ctx.props().data.borrow_mut() = v;
+                ctx.props().page.borrow_mut().total = total;
+                self.loading = false;
+                true
+            }
+            RoleListMsg::PageChanged(page) => {
+                *ctx.props().page.borrow_mut() = page;
+                ctx.link().send_message(RoleListMsg::Refresh);
+                true
+            }
+            RoleListMsg::OnSelect(role) => {
+                self.selected_row = Some(role);
+                true
+            }
+            RoleListMsg::Create => {
+                self.form_value = Some(Role::default());
+                *self.role_form_closed.borrow_mut() = false;
+                true
+            }
+            RoleListMsg::Edit => {
+                if self.selected_row.is_none() {
+                    self.messages.warn("please select a record");
+                } else {
+                    self.form_value = self.selected_row.clone();
+                    *self.role_form_closed.borrow_mut() = false;
+                }
+                true
+            }
+            RoleListMsg::Delete => {
+                if self.selected_row.is_none() {
+                    self.messages.warn("please select a record");
+                } else {
+                    *self.confirm_form_closed.borrow_mut() = false;
+                }
+                true
+            }
+            RoleListMsg::DeleteConfirm => {
+                let body = DeleteReq {
+                    ids: Vec::from([self.selected_row.clone().unwrap().id]),
+                };
+                ctx.link().send_future(async move {
+                    match request::delete::<u64, _>(request::Host::ApiBase, "/role/delete", &body)
+                        .await
+                    {
+                        Ok(_) => RoleListMsg::HandleDeleteSuccess,
+                        Err(err) => RoleListMsg::HandleDeleteFail(Box::new(err)),
+                    }
+                });
+                true
+            }
+            RoleListMsg::HandleDeleteSuccess => {
+                *self.confirm_form_closed.borrow_mut() = true;
+                ctx.link().send_message(RoleListMsg::Refresh);
+                false
+            }
+            RoleListMsg::HandleDeleteFail(_err) => {
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+            RoleListMsg::KeywordChange(e) => {
+                let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+                self.key_word = Some(el.value());
+                ctx.props().page.borrow_mut().to_start();
+                ctx.link().send_message(RoleListMsg::Refresh);
+                false
+            }
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            ctx.link().send_message(RoleListMsg::Refresh);
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let data = ctx.props().data.borrow().clone();
+        let selected_id = self.selected_row.clone().map(|x| x.id);
         html! {
-            <>
-                {"Role"}
-                <input class="input" type="text" placeholder="Text input"/>
-                <div class="select is-multiple">
-                <select multiple={true} size="8">
-                    <option value="Argentina">{"Argentina"}</option>
-                    <option value="Bolivia">{"Bolivia"}</option>
-                    <option value="Brazil">{"Brazil"}</option>
-                    <option value="Chile">{"Chile"}</option>
-                    <option value="Colombia">{"Colombia"}</option>
-                    <option value="Ecuador">{"Ecuador"}</option>
-                    <option value="Guyana">{"Guyana"}</option>
-                    <option value="Paraguay">{"Paraguay"}</option>
-                    <option value="Peru">{"Peru"}</option>
-                    <option value="Suriname">{"Suriname"}</option>
-                    <option value="Uruguay">{"Uruguay"}</option>
-                    <option value="Venezuela">{"Venezuela"}</option>
-                </select>
-                </div>
-            </>
+        <>
+        <MessageList value = {self.messages.clone()} ws = {config::get().features.chat}/>
+        if let Some(v) = &self.form_value {
+            <RoleForm value = {RefCell::new(v.clone())} closed={self.role_form_closed.clone()} update = {ctx.link().callback(|_|{RoleListMsg::Refresh})}/>
+        }
+        if self.selected_row.is_some() {
+            <ConfirmForm closed={self.confirm_form_closed.clone()} confirm = {ctx.link().callback(|_|{RoleListMsg::DeleteConfirm})} content = {"Deleted roles <b>can not</b> be recovered!!!<br/> are you sure you want to delete it?"}/>
+        }
+        <div class="search-container">
+            <div class="search-input field is-grouped">
+            <p class="control is-expanded">
+                <input class="input" type="text" onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|RoleListMsg::KeywordChange(e))} placeholder="Search"/>
+            </p>
+
+            <p class="control">
+                <button class="button is-light is-success" onclick={ctx.link().callback(|_|RoleListMsg::Create)}>{"Create"}</button>
+            </p>
+            <p class="control">
+                <button class="button is-light is-warning" onclick={ctx.link().callback(|_|RoleListMsg::Edit)}>{"Edit"}</button>
+            </p>
+            <p class="control">
+                <button class="button is-light is-danger" onclick={ctx.link().callback(|_|RoleListMsg::Delete)}>{"Delete"}</button>
+            </p>
+            </div>
+        </div>
+        <div class="table-container">
+            {
+                if self.loading {
+                    html!{
+                        <div class="table-loading">
+                        </div>
+                    }
+                }else{
+                    html!{}
+                }
+            }
+
+            <table class="table is-bordered is-striped is-narrow is-hoverable">
+            <thead>
+                <tr>
+                <th><abbr title="Name">{"Name"}</abbr></th>
+                <th><abbr title="Description">{"Description"}</abbr></th>
+                <th><abbr title="Created_at">{"Created_at"}</abbr></th>
+                <th><abbr title="Updated_at">{"Updated_at"}</abbr></th>
+                </tr>
+            </thead>
+            <tbody>
+            {
+                data.iter().map(|x| html!{
+                    <RoleListItem is_selected = {selected_id.is_some() && selected_id.clone().unwrap()==x.id} onselect={ctx.link().callback(|role|{RoleListMsg::OnSelect(role)})}  value={x.clone()} />
+                }).collect::<Html>()
+            }
+            </tbody>
+            </table>
+        </div>
+        <div class="pager-container">
+        {
+            html!{
+                <Pager value = { RefCell::new(ctx.props().page.borrow().clone())} page_changed = {ctx.link().callback(|page|RoleListMsg::PageChanged(page))}/>
+            }
         }
+        </div>
+
+        </>
+                        }
     }
 }
@@ -0,0 +1,51 @@
+use crate::role_list::Role;
+use yew::prelude::*;
+use yew::Properties;
+
+pub struct RoleListItem;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct RoleListItemProps {
+    pub value: Role,
+    #[prop_or_default]
+    pub is_selected: bool,
+    pub onselect: Callback<Role>,
+}
+
+pub enum RoleListItemMsg {
+    Select,
+}
+
+impl Component for RoleListItem {
+    type Message = RoleListItemMsg;
+    type Properties = RoleListItemProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            RoleListItemMsg::Select => {
+                let val = ctx.props().value.clone();
+                ctx.props().onselect.emit(val);
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let role = &ctx.props().value;
+        let is_selected = ctx.props().is_selected;
+
+        html! {
+            <tr class = {if is_selected {"is-selected"} else {""}}
+             onclick = {ctx.link().callback(|_| RoleListItemMsg::Select)} >
+                <td>{role.name.clone()}</td>
+                <td>{role.description.clone().unwrap_or_default()}</td>
+                <td>{role.created_at.clone()}</td>
+                <td>{role.updated_at.clone().unwrap_or_default()}</td>
+            </tr>
+        }
+    }
+}
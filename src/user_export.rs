@@ -0,0 +1,163 @@
+use crate::user_search::{self, UserSearchReq};
+use crate::util::common::BasicResult;
+use gloo::file::{Blob, ObjectUrl};
+use gloo::timers::callback::Timeout;
+use serde_json::Value;
+use user_cli::models::User;
+use wasm_bindgen::JsCast;
+
+// page size used when walking through the whole filtered set
+const EXPORT_PAGE_SIZE: usize = 200;
+
+const CSV_COLUMNS: [&str; 9] = [
+    "id",
+    "type",
+    "email",
+    "name",
+    "mobile",
+    "laston",
+    "created_at",
+    "updated_at",
+    "status",
+];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv;charset=utf-8",
+            ExportFormat::Json => "application/json;charset=utf-8",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportScope {
+    Page,
+    Selected,
+    All,
+}
+
+impl ExportScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportScope::Page => "page",
+            ExportScope::Selected => "selected",
+            ExportScope::All => "all",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportScope::Page => "Current page",
+            ExportScope::Selected => "Selected rows",
+            ExportScope::All => "All filtered",
+        }
+    }
+}
+
+fn csv_field(value: Option<&Value>) -> String {
+    let s = match value {
+        None | Some(Value::Null) => String::new(),
+        // excel and sheets run text starting like this as a formula
+        Some(Value::String(v)) if v.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+            format!("'{}", v)
+        }
+        Some(Value::String(v)) => v.clone(),
+        Some(v) => v.to_string(),
+    };
+    if s.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    }
+}
+
+pub fn to_csv(users: &[User]) -> BasicResult<String> {
+    // BOM so that excel picks utf-8 for non-ascii names
+    let mut res = String::from("\u{feff}");
+    res.push_str(&CSV_COLUMNS.join(","));
+    res.push_str("\r\n");
+    for user in users {
+        let value = serde_json::to_value(user)?;
+        let line = CSV_COLUMNS
+            .iter()
+            .map(|x| csv_field(value.get(*x)))
+            .collect::<Vec<String>>()
+            .join(",");
+        res.push_str(&line);
+        res.push_str("\r\n");
+    }
+    Ok(res)
+}
+
+pub fn to_json(users: &[User]) -> BasicResult<String> {
+    Ok(serde_json::to_string_pretty(users)?)
+}
+
+pub fn file_name(scope: ExportScope, format: ExportFormat) -> String {
+    format!(
+        "users_{}_{}.{}",
+        scope.as_str(),
+        chrono::Local::now().format("%Y%m%d%H%M%S"),
+        format.as_str()
+    )
+}
+
+/// serialize users and let the browser save them through a temporary blob url
+pub fn download(users: &[User], scope: ExportScope, format: ExportFormat) -> BasicResult<()> {
+    let content = match format {
+        ExportFormat::Csv => to_csv(users)?,
+        ExportFormat::Json => to_json(users)?,
+    };
+    let blob = Blob::new_with_options(content.as_str(), Some(format.mime_type()));
+    let url = ObjectUrl::from(blob);
+    let document = web_sys::window().unwrap().document().unwrap();
+    let a = document
+        .create_element("a")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .unwrap();
+    a.set_href(&url);
+    a.set_download(&file_name(scope, format));
+    a.click();
+    // the url is revoked on drop, keep it alive until the browser has picked it up
+    Timeout::new(1_000, move || drop(url)).forget();
+    Ok(())
+}
+
+/// walk every page of the search, reporting (fetched, total) after each page
+pub async fn fetch_all<F>(req: UserSearchReq, on_progress: F) -> BasicResult<Vec<User>>
+where
+    F: Fn(usize, usize),
+{
+    let mut res = vec![];
+    let mut req = UserSearchReq {
+        index: 1,
+        size: EXPORT_PAGE_SIZE,
+        ..req
+    };
+    loop {
+        let (data, total) = user_search::search(&req).await?;
+        let is_last = data.len() < req.size;
+        res.extend(data.into_iter().map(|x| *x.user));
+        on_progress(res.len(), total.max(res.len()));
+        if is_last || res.len() >= total {
+            break;
+        }
+        req.index += 1;
+    }
+    Ok(res)
+}
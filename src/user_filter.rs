@@ -0,0 +1,118 @@
+use crate::user_search::{UserFilter, UserStatus, UserType};
+use yew::prelude::*;
+use yew::Properties;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct UserFilterProps {
+    pub value: UserFilter,
+    pub on_change: Callback<UserFilter>,
+}
+
+fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+fn parse_set(s: &str) -> Option<bool> {
+    match s {
+        "set" => Some(true),
+        "not_set" => Some(false),
+        _ => None,
+    }
+}
+
+#[function_component(UserFilterPanel)]
+pub fn user_filter_panel(props: &UserFilterProps) -> Html {
+    // build an onchange handler that applies the input value to a copy of the filter
+    let change = |apply: fn(&mut UserFilter, String)| {
+        let value = props.value.clone();
+        let on_change = props.on_change.clone();
+        Callback::from(move |e: web_sys::Event| {
+            let el: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let mut filter = value.clone();
+            apply(&mut filter, el.value());
+            on_change.emit(filter);
+        })
+    };
+
+    let value = &props.value;
+    html! {
+        <div class="box filter-panel">
+            <div class="columns is-multiline">
+                <div class="column is-3 field">
+                    <label class="label is-small">{"Type"}</label>
+                    <div class="select is-small is-fullwidth">
+                        <select onchange={change(|f, v| f.r#type = UserType::parse(&v))}>
+                            <option value="" selected={value.r#type.is_none()}>{"All"}</option>
+                            {
+                                [UserType::Admin, UserType::Normal].iter().map(|x| html!{
+                                    <option value={x.as_str()} selected={value.r#type == Some(*x)}>{x.as_str()}</option>
+                                }).collect::<Html>()
+                            }
+                        </select>
+                    </div>
+                </div>
+                <div class="column is-3 field">
+                    <label class="label is-small">{"Status"}</label>
+                    <div class="select is-small is-fullwidth">
+                        <select onchange={change(|f, v| f.status = UserStatus::parse(&v))}>
+                            <option value="" selected={value.status.is_none()}>{"All"}</option>
+                            {
+                                [UserStatus::Available, UserStatus::Disabled].iter().map(|x| html!{
+                                    <option value={x.as_str()} selected={value.status == Some(*x)}>{x.as_str()}</option>
+                                }).collect::<Html>()
+                            }
+                        </select>
+                    </div>
+                </div>
+                <div class="column is-3 field">
+                    <label class="label is-small">{"Mobile"}</label>
+                    <div class="select is-small is-fullwidth">
+                        <select onchange={change(|f, v| f.has_mobile = parse_set(&v))}>
+                            <option value="" selected={value.has_mobile.is_none()}>{"Any"}</option>
+                            <option value="set" selected={value.has_mobile == Some(true)}>{"Set"}</option>
+                            <option value="not_set" selected={value.has_mobile == Some(false)}>{"Not set"}</option>
+                        </select>
+                    </div>
+                </div>
+                <div class="column is-3 field">
+                    <label class="label is-small">{"Name"}</label>
+                    <div class="select is-small is-fullwidth">
+                        <select onchange={change(|f, v| f.has_name = parse_set(&v))}>
+                            <option value="" selected={value.has_name.is_none()}>{"Any"}</option>
+                            <option value="set" selected={value.has_name == Some(true)}>{"Set"}</option>
+                            <option value="not_set" selected={value.has_name == Some(false)}>{"Not set"}</option>
+                        </select>
+                    </div>
+                </div>
+                <div class="column is-6 field">
+                    <label class="label is-small">{"Created_at"}</label>
+                    <div class="field has-addons">
+                        <p class="control is-expanded">
+                            <input class="input is-small" type="date" value={value.created_from.clone()} onchange={change(|f, v| f.created_from = non_empty(v))}/>
+                        </p>
+                        <p class="control"><a class="button is-small is-static">{"~"}</a></p>
+                        <p class="control is-expanded">
+                            <input class="input is-small" type="date" value={value.created_to.clone()} onchange={change(|f, v| f.created_to = non_empty(v))}/>
+                        </p>
+                    </div>
+                </div>
+                <div class="column is-6 field">
+                    <label class="label is-small">{"Laston"}</label>
+                    <div class="field has-addons">
+                        <p class="control is-expanded">
+                            <input class="input is-small" type="date" value={value.laston_from.clone()} onchange={change(|f, v| f.laston_from = non_empty(v))}/>
+                        </p>
+                        <p class="control"><a class="button is-small is-static">{"~"}</a></p>
+                        <p class="control is-expanded">
+                            <input class="input is-small" type="date" value={value.laston_to.clone()} onchange={change(|f, v| f.laston_to = non_empty(v))}/>
+                        </p>
+                    </div>
+                </div>
+            </div>
+        </div>
+    }
+}
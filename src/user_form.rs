@@ -1,4 +1,7 @@
-use crate::util::common;
+use crate::component::in_flight::InFlightState;
+use crate::util::common::{self, ValidStatus};
+use crate::util::error::ErrorKind;
+use crate::util::request::{self, InFlight};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -10,8 +13,29 @@ use user_cli::models::{User, UserUpdateReq};
 use yew::prelude::*;
 use yew::Properties;
 
+// in-flight key of the save request
+const SUBMIT_KEY: &str = "user_form";
+
 pub struct UserForm {
     messages: Arc<Mutex<MessageListValue>>,
+    in_flight: InFlightState,
+    // set from the field errors of a refused save
+    name_valid: ValidStatus,
+    mobile_valid: ValidStatus,
+}
+
+fn input_class(status: &ValidStatus) -> &'static str {
+    match status {
+        ValidStatus::InValid(_) => "input is-danger",
+        _ => "input",
+    }
+}
+
+fn invalid_msg(status: &ValidStatus) -> String {
+    match status {
+        ValidStatus::InValid(e) => e.clone(),
+        _ => String::new(),
+    }
 }
 
 pub enum UserFormMsg {
@@ -21,6 +45,7 @@ pub enum UserFormMsg {
     Update,
     UpdateSuccess,
     UpdateError(Box<dyn std::error::Error>),
+    InFlightChange(InFlight),
 }
 
 #[derive(Clone, PartialEq, Properties)]
@@ -37,9 +62,13 @@ impl Component for UserForm {
 
     type Properties = UserFormProps;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let in_flight = InFlightState::subscribe(ctx, UserFormMsg::InFlightChange);
         Self {
             messages: Default::default(),
+            in_flight,
+            name_valid: ValidStatus::None,
+            mobile_valid: ValidStatus::None,
         }
     }
 
@@ -52,14 +81,19 @@ impl Component for UserForm {
             UserFormMsg::NameChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
                 ctx.props().value.borrow_mut().name = Some(el.value());
-                false
+                self.name_valid = ValidStatus::None;
+                true
             }
             UserFormMsg::MobileChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
                 ctx.props().value.borrow_mut().mobile = Some(el.value());
-                false
+                self.mobile_valid = ValidStatus::None;
+                true
             }
             UserFormMsg::Update => {
+                if self.in_flight.is_pending(SUBMIT_KEY) {
+                    return false;
+                }
                 let user = ctx.props().value.borrow().clone();
                 let req = UserUpdateReq {
                     id: user.id,
@@ -67,7 +101,15 @@ impl Component for UserForm {
                     mobile: user.mobile,
                 };
                 ctx.link().send_future(async move {
-                    match user_controller_api::update(&common::get_cli_config().unwrap(), req).await
+                    let config = match common::get_cli_config() {
+                        Ok(v) => v,
+                        Err(err) => return UserFormMsg::UpdateError(Box::new(err)),
+                    };
+                    match request::track_as(
+                        SUBMIT_KEY,
+                        request::cli("update", user_controller_api::update(&config, req)),
+                    )
+                    .await
                     {
                         Ok(_) => UserFormMsg::UpdateSuccess,
                         Err(err) => UserFormMsg::UpdateError(Box::new(err)),
@@ -80,8 +122,19 @@ impl Component for UserForm {
                 ctx.link().send_message(UserFormMsg::Close);
                 false
             }
-            UserFormMsg::UpdateError(_err) => {
-                self.messages.error(&format!("{}", _err));
+            UserFormMsg::InFlightChange(v) => self.in_flight.update(v, SUBMIT_KEY),
+            UserFormMsg::UpdateError(err) => {
+                if let Some(err) = err.downcast_ref::<ErrorKind>() {
+                    for (name, valid) in [
+                        ("name", &mut self.name_valid),
+                        ("mobile", &mut self.mobile_valid),
+                    ] {
+                        if let Some(msg) = err.field(name) {
+                            *valid = ValidStatus::InValid(msg.to_string());
+                        }
+                    }
+                }
+                self.messages.request_failed(err.as_ref());
                 true
             }
         }
@@ -92,6 +145,7 @@ impl Component for UserForm {
             return html! {};
         }
         let val = ctx.props().value.borrow();
+        let submitting = self.in_flight.is_pending(SUBMIT_KEY);
         html! {
             <div class="modal is-active">
                 <div class="modal-background"></div>
@@ -131,20 +185,22 @@ impl Component for UserForm {
                     <div class="field">
                         <label class="label">{"Name"}</label>
                         <div class="control">
-                        <input class="input" value={val.name.clone()} type="text" placeholder="Scarlett" onchange={ctx.link().callback(|e:web_sys::Event|UserFormMsg::NameChange(e))}/>
+                        <input class={input_class(&self.name_valid)} value={val.name.clone()} type="text" placeholder="Scarlett" onchange={ctx.link().callback(|e:web_sys::Event|UserFormMsg::NameChange(e))}/>
                         </div>
+                        <p class="help is-danger">{invalid_msg(&self.name_valid)}</p>
                     </div>
 
                     <div class="field">
                         <label class="label">{"Mobile"}</label>
                         <div class="control">
-                        <input class="input" value={val.mobile.clone()} type="text" placeholder="13800001111" onchange={ctx.link().callback(|e:web_sys::Event|UserFormMsg::MobileChange(e))}/>
+                        <input class={input_class(&self.mobile_valid)} value={val.mobile.clone()} type="text" placeholder="13800001111" onchange={ctx.link().callback(|e:web_sys::Event|UserFormMsg::MobileChange(e))}/>
                         </div>
+                        <p class="help is-danger">{invalid_msg(&self.mobile_valid)}</p>
                     </div>
 
                     </section>
                     <footer class="modal-card-foot">
-                    <button class="button is-success"  onclick={ctx.link().callback(|_|UserFormMsg::Update)}>{"Save changes"}</button>
+                    <button class={classes!("button", "is-success", submitting.then_some("is-loading"))} disabled={submitting} onclick={ctx.link().callback(|_|UserFormMsg::Update)}>{"Save changes"}</button>
                     <button class="button" onclick={ctx.link().callback(|_|UserFormMsg::Close)} >{"Cancel"}</button>
                     </footer>
                 </div>
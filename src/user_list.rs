@@ -1,41 +1,83 @@
 use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
 use crate::component::pager::{Page, Pager};
 use crate::confirm_form::ConfirmForm;
+use crate::role_list::Role;
+use crate::user_export::{self, ExportFormat, ExportScope};
+use crate::user_filter::UserFilterPanel;
 use crate::user_form::UserForm;
 use crate::user_list_item::UserListItem;
+use crate::user_role_form::UserRoleForm;
+use crate::user_search::{
+    self, FilterKey, Sort, SortField, SortOrder, UserFilter, UserListQuery, UserSearchReq,
+    UserStatus,
+};
 use crate::util::request;
+use crate::RouteBody;
 
-use crate::util::common;
+use crate::util::config;
+use crate::util::debounce::{Debounce, Generation};
 use serde::Serialize;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
-use user_cli::apis::user_controller_api;
 use user_cli::models::{SearchedUser, User};
 use yew::prelude::*;
 use yew::Properties;
+use yew_router::prelude::*;
 
 pub struct UserList {
-    selected_row: Option<User>,
+    selected_rows: Vec<User>,
+    // anchor row of shift-click range selection
+    last_checked: Option<i64>,
     user_form_closed: Rc<RefCell<bool>>,
+    user_role_form_closed: Rc<RefCell<bool>>,
     confirm_form_closed: Rc<RefCell<bool>>,
+    user_roles: HashMap<i64, Vec<Role>>,
     messages: Arc<Mutex<MessageListValue>>,
     loading: bool,
     key_word: Option<String>,
+    sort: Option<Sort>,
+    filter: UserFilter,
+    filter_open: bool,
+    search_debounce: Debounce,
+    search_generation: Generation,
+    // (fetched, total) while the whole filtered set is being exported
+    exporting: Option<(usize, usize)>,
+    _location_handle: Option<LocationHandle>,
 }
 
 pub enum UserListMsg {
     Refresh,
-    HandleSearchSuccess(Vec<SearchedUser>, usize),
-    HandleSearchFail(Box<dyn std::error::Error>),
+    HandleSearchSuccess(u64, Vec<SearchedUser>, usize),
+    HandleSearchFail(u64, Box<dyn std::error::Error>),
     PageChanged(Page),
     OnSelect(User),
+    OnCheck(User, bool),
+    ToggleAll,
     Edit,
+    AssignRoles,
     Delete,
     DeleteConfirm,
     HandleDeleteSuccess,
     HandleDeleteFail(Box<dyn std::error::Error>),
+    ChangeStatus(UserStatus),
+    HandleChangeStatusSuccess,
+    HandleChangeStatusFail(Box<dyn std::error::Error>),
     KeywordChange(web_sys::KeyboardEvent),
+    SortChange(SortField),
+    ToggleFilterPanel,
+    FilterChange(UserFilter),
+    RemoveFilter(FilterKey),
+    ClearFilters,
+    LocationChanged(Location),
+    LoadUserRoles(Vec<i64>),
+    HandleLoadUserRolesSuccess(u64, HashMap<i64, Vec<Role>>),
+    HandleLoadUserRolesFail(u64, Box<dyn std::error::Error>),
+    Export(ExportScope, ExportFormat),
+    ExportProgress(usize, usize),
+    HandleExportSuccess(ExportFormat, Vec<User>),
+    HandleExportFail(Box<dyn std::error::Error>),
 }
 
 #[derive(Serialize)]
@@ -43,6 +85,12 @@ pub struct DeleteReq {
     pub ids: Vec<i64>,
 }
 
+#[derive(Serialize)]
+pub struct ChangeStatusReq {
+    pub ids: Vec<i64>,
+    pub status: UserStatus,
+}
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct UserListProps {
     #[prop_or_default]
@@ -52,54 +100,129 @@ pub struct UserListProps {
     pub page: RefCell<Page>,
 }
 
+impl UserList {
+    fn query(&self, ctx: &Context<Self>) -> UserListQuery {
+        UserListQuery::new(
+            self.key_word.as_deref(),
+            &ctx.props().page.borrow(),
+            self.sort,
+            &self.filter,
+        )
+    }
+
+    // every list state change becomes a history entry
+    fn push_query(&self, ctx: &Context<Self>) {
+        if ctx.link().route::<RouteBody>() != Some(RouteBody::User) {
+            return;
+        }
+        let query = self.query(ctx);
+        let current = ctx
+            .link()
+            .location()
+            .and_then(|x| x.query::<UserListQuery>().ok())
+            .unwrap_or_default();
+        if query == current {
+            return;
+        }
+        if let Some(navigator) = ctx.link().navigator() {
+            if let Err(err) = navigator.push_with_query(&RouteBody::User, &query) {
+                log::error!("push query error: {}", err);
+            }
+        }
+    }
+
+    fn sort_header(&self, ctx: &Context<Self>, field: SortField, title: &'static str) -> Html {
+        let icon = match self.sort {
+            Some(sort) if sort.field == field => match sort.order {
+                SortOrder::Asc => "fa-solid fa-arrow-up",
+                SortOrder::Desc => "fa-solid fa-arrow-down",
+            },
+            _ => "fa-solid fa-sort has-text-grey-light",
+        };
+        html! {
+            <th><abbr title={title}><a href="javascript:void(0)" onclick={ctx.link().callback(move|_|UserListMsg::SortChange(field))}>{format!("{}  ", title)}<i class={icon}></i></a></abbr></th>
+        }
+    }
+}
+
 impl Component for UserList {
     type Message = UserListMsg;
 
     type Properties = UserListProps;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        // restore list state from the url
+        let query = ctx
+            .link()
+            .location()
+            .and_then(|x| x.query::<UserListQuery>().ok())
+            .unwrap_or_default();
+        query.apply_page(&mut ctx.props().page.borrow_mut());
+        let location_handle = ctx
+            .link()
+            .add_location_listener(ctx.link().callback(UserListMsg::LocationChanged));
         Self {
-            selected_row: Default::default(),
+            selected_rows: Default::default(),
+            last_checked: Default::default(),
             user_form_closed: Rc::new(RefCell::new(true)),
+            user_role_form_closed: Rc::new(RefCell::new(true)),
             confirm_form_closed: Rc::new(RefCell::new(true)),
+            user_roles: Default::default(),
             messages: Default::default(),
             loading: Default::default(),
-            key_word: Default::default(),
+            key_word: query.key_word.clone(),
+            sort: query.sort(),
+            filter: query.filter(),
+            filter_open: false,
+            search_debounce: Default::default(),
+            search_generation: Default::default(),
+            exporting: None,
+            _location_handle: location_handle,
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             UserListMsg::Refresh => {
-                self.selected_row = None;
+                self.selected_rows.clear();
+                self.last_checked = None;
                 self.loading = true;
+                self.push_query(ctx);
 
-                let key_word = self.key_word.clone().unwrap_or_default();
                 let page = ctx.props().page.borrow();
-                let index = page.index;
-                let size = page.size;
+                let req = UserSearchReq {
+                    key_word: self.key_word.clone().unwrap_or_default(),
+                    index: page.index,
+                    size: page.size,
+                    sort: self.sort,
+                    filter: self.filter.clone(),
+                };
 
+                let generation = self.search_generation.next();
                 ctx.link().send_future(async move {
-                    match user_controller_api::search(
-                        &common::get_cli_config().unwrap(),
-                        &key_word,
-                        index as i64,
-                        size as i64,
-                    )
-                    .await
-                    {
-                        Ok(res) => UserListMsg::HandleSearchSuccess(res.data, res.total as usize),
-                        Err(err) => UserListMsg::HandleSearchFail(Box::new(err)),
+                    match user_search::search(&req).await {
+                        Ok((data, total)) => {
+                            UserListMsg::HandleSearchSuccess(generation, data, total)
+                        }
+                        Err(err) => UserListMsg::HandleSearchFail(generation, Box::new(err)),
                     }
                 });
                 false
             }
-            UserListMsg::HandleSearchFail(_err) => {
-                self.messages.error(&format!("{}", _err));
+            UserListMsg::HandleSearchFail(generation, _err) => {
+                if !self.search_generation.is_current(generation) {
+                    return false;
+                }
+                self.messages.request_failed(_err.as_ref());
                 self.loading = false;
                 true
             }
-            UserListMsg::HandleSearchSuccess(v, total) => {
+            UserListMsg::HandleSearchSuccess(generation, v, total) => {
+                if !self.search_generation.is_current(generation) {
+                    return false;
+                }
+                let ids = v.iter().map(|x| x.user.id).collect();
+                ctx.link().send_message(UserListMsg::LoadUserRoles(ids));
                 *ctx.props().data.borrow_mut() = v;
                 ctx.props().page.borrow_mut().total = total;
                 self.loading = false;
@@ -111,20 +234,66 @@ impl Component for UserList {
                 true
             }
             UserListMsg::OnSelect(user) => {
-                self.selected_row = Some(user);
+                self.last_checked = Some(user.id);
+                self.selected_rows = vec![user];
+                true
+            }
+            UserListMsg::OnCheck(user, shift) => {
+                let data = ctx.props().data.borrow();
+                let position = |id: i64| data.iter().position(|x| x.user.id == id);
+                let range = match (shift, self.last_checked.and_then(position)) {
+                    (true, Some(anchor)) => {
+                        position(user.id).map(|current| (anchor.min(current), anchor.max(current)))
+                    }
+                    _ => None,
+                };
+                match range {
+                    Some((start, end)) => {
+                        for x in data[start..=end].iter() {
+                            if !self.selected_rows.iter().any(|s| s.id == x.user.id) {
+                                self.selected_rows.push(*x.user.clone());
+                            }
+                        }
+                    }
+                    None => {
+                        if let Some(i) = self.selected_rows.iter().position(|x| x.id == user.id) {
+                            self.selected_rows.remove(i);
+                        } else {
+                            self.selected_rows.push(user.clone());
+                        }
+                    }
+                }
+                self.last_checked = Some(user.id);
+                true
+            }
+            UserListMsg::ToggleAll => {
+                let data = ctx.props().data.borrow();
+                if !data.is_empty() && self.selected_rows.len() == data.len() {
+                    self.selected_rows.clear();
+                } else {
+                    self.selected_rows = data.iter().map(|x| *x.user.clone()).collect();
+                }
+                self.last_checked = None;
                 true
             }
             UserListMsg::Edit => {
-                if self.selected_row.is_none() {
-                    log::info!("{:#?}", self.messages);
-                    self.messages.warn("please select a record");
+                if self.selected_rows.len() != 1 {
+                    self.messages.warn("please select one record");
                 } else {
                     *self.user_form_closed.borrow_mut() = false;
                 }
                 true
             }
+            UserListMsg::AssignRoles => {
+                if self.selected_rows.is_empty() {
+                    self.messages.warn("please select a record");
+                } else {
+                    *self.user_role_form_closed.borrow_mut() = false;
+                }
+                true
+            }
             UserListMsg::Delete => {
-                if self.selected_row.is_none() {
+                if self.selected_rows.is_empty() {
                     self.messages.warn("please select a record");
                 } else {
                     *self.confirm_form_closed.borrow_mut() = false;
@@ -133,7 +302,7 @@ impl Component for UserList {
             }
             UserListMsg::DeleteConfirm => {
                 let body = DeleteReq {
-                    ids: Vec::from([self.selected_row.clone().unwrap().id]),
+                    ids: self.selected_rows.iter().map(|x| x.id).collect(),
                 };
                 ctx.link().send_future(async move {
                     match request::delete::<u64, _>(request::Host::ApiBase, "/user/delete", &body)
@@ -151,16 +320,210 @@ impl Component for UserList {
                 false
             }
             UserListMsg::HandleDeleteFail(_err) => {
-                self.messages.error(&format!("{}", _err));
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+            UserListMsg::ChangeStatus(status) => {
+                if self.selected_rows.is_empty() {
+                    self.messages.warn("please select a record");
+                    return true;
+                }
+                let body = ChangeStatusReq {
+                    ids: self.selected_rows.iter().map(|x| x.id).collect(),
+                    status,
+                };
+                ctx.link().send_future(async move {
+                    match request::put::<u64, _>(
+                        request::Host::ApiBase,
+                        "/user/update_status",
+                        &body,
+                    )
+                    .await
+                    {
+                        Ok(_) => UserListMsg::HandleChangeStatusSuccess,
+                        Err(err) => UserListMsg::HandleChangeStatusFail(Box::new(err)),
+                    }
+                });
+                false
+            }
+            UserListMsg::HandleChangeStatusSuccess => {
+                self.messages.ok("status changed");
+                ctx.link().send_message(UserListMsg::Refresh);
+                false
+            }
+            UserListMsg::HandleChangeStatusFail(_err) => {
+                self.messages.request_failed(_err.as_ref());
                 true
             }
             UserListMsg::KeywordChange(e) => {
                 let el: web_sys::HtmlInputElement = e.target_unchecked_into();
-                self.key_word = Some(el.value());
+                let key_word = Some(el.value());
+                if key_word == self.key_word {
+                    return false;
+                }
+                self.key_word = key_word;
+                ctx.props().page.borrow_mut().to_start();
+                let link = ctx.link().clone();
+                self.search_debounce
+                    .call(move || link.send_message(UserListMsg::Refresh));
+                false
+            }
+            UserListMsg::SortChange(field) => {
+                self.sort = Sort::toggle(self.sort, field);
                 ctx.props().page.borrow_mut().to_start();
                 ctx.link().send_message(UserListMsg::Refresh);
+                true
+            }
+            UserListMsg::ToggleFilterPanel => {
+                self.filter_open = !self.filter_open;
+                true
+            }
+            UserListMsg::FilterChange(filter) => {
+                self.filter = filter;
+                ctx.props().page.borrow_mut().to_start();
+                ctx.link().send_message(UserListMsg::Refresh);
+                true
+            }
+            UserListMsg::RemoveFilter(key) => {
+                let mut filter = self.filter.clone();
+                filter.clear(key);
+                ctx.link().send_message(UserListMsg::FilterChange(filter));
+                false
+            }
+            UserListMsg::ClearFilters => {
+                ctx.link()
+                    .send_message(UserListMsg::FilterChange(Default::default()));
+                false
+            }
+            UserListMsg::LocationChanged(location) => {
+                if location.path() != RouteBody::User.to_path() {
+                    return false;
+                }
+                // back/forward navigation, our own pushes carry the current state
+                let query = location.query::<UserListQuery>().unwrap_or_default();
+                if query == self.query(ctx) {
+                    return false;
+                }
+                self.key_word = query.key_word.clone();
+                self.sort = query.sort();
+                self.filter = query.filter();
+                query.apply_page(&mut ctx.props().page.borrow_mut());
+                self.search_debounce.cancel();
+                ctx.link().send_message(UserListMsg::Refresh);
+                true
+            }
+            UserListMsg::LoadUserRoles(ids) => {
+                if ids.is_empty() {
+                    self.user_roles.clear();
+                    return true;
+                }
+                let ids = ids
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+                let generation = self.search_generation.current();
+                ctx.link().send_future(async move {
+                    match request::get::<HashMap<i64, Vec<Role>>, _, _>(
+                        request::Host::ApiBase,
+                        "/user/roles",
+                        Some([("ids", ids)]),
+                    )
+                    .await
+                    {
+                        Ok(res) => UserListMsg::HandleLoadUserRolesSuccess(
+                            generation,
+                            res.data.unwrap_or_default(),
+                        ),
+                        Err(err) => UserListMsg::HandleLoadUserRolesFail(generation, Box::new(err)),
+                    }
+                });
                 false
             }
+            UserListMsg::HandleLoadUserRolesSuccess(generation, v) => {
+                if !self.search_generation.is_current(generation) {
+                    return false;
+                }
+                self.user_roles = v;
+                true
+            }
+            UserListMsg::HandleLoadUserRolesFail(generation, _err) => {
+                if !self.search_generation.is_current(generation) {
+                    return false;
+                }
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+            UserListMsg::Export(scope, format) => {
+                let users = match scope {
+                    ExportScope::Page => ctx
+                        .props()
+                        .data
+                        .borrow()
+                        .iter()
+                        .map(|x| *x.user.clone())
+                        .collect(),
+                    ExportScope::Selected => self.selected_rows.clone(),
+                    ExportScope::All => {
+                        if self.exporting.is_some() {
+                            self.messages.warn("an export is already running");
+                            return true;
+                        }
+                        self.exporting = Some((0, ctx.props().page.borrow().total));
+                        let req = UserSearchReq {
+                            key_word: self.key_word.clone().unwrap_or_default(),
+                            sort: self.sort,
+                            filter: self.filter.clone(),
+                            ..Default::default()
+                        };
+                        let link = ctx.link().clone();
+                        ctx.link().send_future(async move {
+                            let on_progress = move |fetched, total| {
+                                link.send_message(UserListMsg::ExportProgress(fetched, total))
+                            };
+                            match user_export::fetch_all(req, on_progress).await {
+                                Ok(users) => UserListMsg::HandleExportSuccess(format, users),
+                                Err(err) => UserListMsg::HandleExportFail(Box::new(err)),
+                            }
+                        });
+                        return true;
+                    }
+                };
+                if users.is_empty() {
+                    self.messages.warn("nothing to export");
+                    return true;
+                }
+                if let Err(err) = user_export::download(&users, scope, format) {
+                    self.messages.error(&format!("{}", err));
+                }
+                true
+            }
+            UserListMsg::ExportProgress(fetched, total) => {
+                if self.exporting.is_none() {
+                    return false;
+                }
+                self.exporting = Some((fetched, total));
+                true
+            }
+            UserListMsg::HandleExportSuccess(format, users) => {
+                self.exporting = None;
+                if users.is_empty() {
+                    self.messages.warn("nothing to export");
+                } else {
+                    match user_export::download(&users, ExportScope::All, format) {
+                        Ok(_) => self
+                            .messages
+                            .ok(&format!("{} user(s) exported", users.len())),
+                        Err(err) => self.messages.error(&format!("{}", err)),
+                    }
+                }
+                true
+            }
+            UserListMsg::HandleExportFail(_err) => {
+                self.exporting = None;
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
         }
     }
 
@@ -172,27 +535,120 @@ impl Component for UserList {
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let data = ctx.props().data.borrow().clone();
-        let selected_id = self.selected_row.clone().map(|x| x.id);
+        let is_selected = |id: i64| self.selected_rows.iter().any(|x| x.id == id);
+        let all_selected = !data.is_empty() && data.iter().all(|x| is_selected(x.user.id));
+        // roles of the selected users, split into held by every one and by some only
+        let mut held = Vec::<Role>::new();
+        for role in self
+            .selected_rows
+            .iter()
+            .filter_map(|u| self.user_roles.get(&u.id))
+            .flatten()
+        {
+            if !held.iter().any(|x| x.id == role.id) {
+                held.push(role.clone());
+            }
+        }
+        let (assigned, partial): (Vec<Role>, Vec<Role>) = held.into_iter().partition(|role| {
+            self.selected_rows.iter().all(|u| {
+                self.user_roles
+                    .get(&u.id)
+                    .is_some_and(|roles| roles.iter().any(|x| x.id == role.id))
+            })
+        });
+        let delete_content = format!(
+            "<b>{}</b> user(s) will be deleted.<br/> Deleted users <b>can not</b> be recovered!!!<br/> are you sure you want to delete them?",
+            self.selected_rows.len()
+        );
         html! {
         <>
-        <MessageList value = {self.messages.clone()} ws = true/>
-        if let Some(v) = &self.selected_row  {
-            <UserForm value = {RefCell::new(v.clone())} closed={self.user_form_closed.clone()} update = {ctx.link().callback(|_|{UserListMsg::Refresh})}/>
-            <ConfirmForm closed={self.confirm_form_closed.clone()} confirm = {ctx.link().callback(|_|{UserListMsg::DeleteConfirm})} content = {"Deleted users <b>can not</b> be recovered!!!<br/> are you sure you want to delete it?"}/>
+        <MessageList value = {self.messages.clone()} ws = {config::get().features.chat}/>
+        if self.selected_rows.len() == 1 {
+            <UserForm value = {RefCell::new(self.selected_rows[0].clone())} closed={self.user_form_closed.clone()} update = {ctx.link().callback(|_|{UserListMsg::Refresh})}/>
+        }
+        if !self.selected_rows.is_empty() {
+            <UserRoleForm users = {self.selected_rows.clone()} assigned = {assigned} partial = {partial} closed={self.user_role_form_closed.clone()} update = {ctx.link().callback(|_|{UserListMsg::Refresh})}/>
+            <ConfirmForm closed={self.confirm_form_closed.clone()} confirm = {ctx.link().callback(|_|{UserListMsg::DeleteConfirm})} content = {delete_content}/>
         }
         <div class="search-container">
             <div class="search-input field is-grouped">
             <p class="control is-expanded">
-                <input class="input" type="text" onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|UserListMsg::KeywordChange(e))} placeholder="Search"/>
+                <input class="input" type="text" value={self.key_word.clone().unwrap_or_default()} onkeyup={ctx.link().callback(|e:web_sys::KeyboardEvent|UserListMsg::KeywordChange(e))} placeholder="Search"/>
+            </p>
+            <p class="control">
+                <button class={if self.filter_open {"button is-light is-link is-active"} else {"button is-light is-link"}} onclick={ctx.link().callback(|_|UserListMsg::ToggleFilterPanel)}>
+                    <span class="icon is-small"><i class="fa-solid fa-filter"></i></span>
+                    <span>{"Filter"}</span>
+                </button>
             </p>
 
             <p class="control">
                 <button class="button is-light is-warning" onclick={ctx.link().callback(|_|UserListMsg::Edit)}>{"Edit"}</button>
             </p>
+            <p class="control">
+                <button class="button is-light is-info" onclick={ctx.link().callback(|_|UserListMsg::AssignRoles)}>{"Assign roles"}</button>
+            </p>
+            <div class="control">
+                <div class="dropdown is-hoverable is-right">
+                    <div class="dropdown-trigger">
+                        <button class="button is-light is-primary" aria-haspopup="true">{"Status"}</button>
+                    </div>
+                    <div class="dropdown-menu" role="menu">
+                        <div class="dropdown-content">
+                            <a href={String::from("javascript:void(0)")} class="dropdown-item" onclick={ctx.link().callback(|_|UserListMsg::ChangeStatus(UserStatus::Available))}>{"Enable"}</a>
+                            <a href={String::from("javascript:void(0)")} class="dropdown-item" onclick={ctx.link().callback(|_|UserListMsg::ChangeStatus(UserStatus::Disabled))}>{"Disable"}</a>
+                        </div>
+                    </div>
+                </div>
+            </div>
             <p class="control">
                 <button class="button is-light is-danger" onclick={ctx.link().callback(|_|UserListMsg::Delete)}>{"Delete"}</button>
             </p>
+            <div class="control">
+                <div class="dropdown is-hoverable is-right">
+                    <div class="dropdown-trigger">
+                        <button class={if self.exporting.is_some() {"button is-light is-loading"} else {"button is-light"}} aria-haspopup="true">
+                            <span class="icon is-small"><i class="fa-solid fa-download"></i></span>
+                            <span>{"Export"}</span>
+                        </button>
+                    </div>
+                    <div class="dropdown-menu" role="menu">
+                        <div class="dropdown-content">
+                        {
+                            [ExportScope::Page, ExportScope::Selected, ExportScope::All].into_iter().map(|scope| html!{
+                                <>
+                                <a href={String::from("javascript:void(0)")} class="dropdown-item" onclick={ctx.link().callback(move|_|UserListMsg::Export(scope, ExportFormat::Csv))}>{format!("{} (CSV)", scope.label())}</a>
+                                <a href={String::from("javascript:void(0)")} class="dropdown-item" onclick={ctx.link().callback(move|_|UserListMsg::Export(scope, ExportFormat::Json))}>{format!("{} (JSON)", scope.label())}</a>
+                                </>
+                            }).collect::<Html>()
+                        }
+                        </div>
+                    </div>
+                </div>
             </div>
+            </div>
+            if let Some((fetched, total)) = self.exporting {
+                <div class="export-progress">
+                    <p class="help">{format!("exporting {} / {}", fetched, total)}</p>
+                    <progress class="progress is-small is-info" value={fetched.to_string()} max={total.max(1).to_string()}></progress>
+                </div>
+            }
+            if self.filter_open {
+                <UserFilterPanel value={self.filter.clone()} on_change={ctx.link().callback(UserListMsg::FilterChange)}/>
+            }
+            if !self.filter.is_empty() {
+                <div class="tags">
+                {
+                    self.filter.tags().into_iter().map(|(key, label)| html!{
+                        <span class="tag is-link is-light">
+                            {label}
+                            <button class="delete is-small" onclick={ctx.link().callback(move|_|UserListMsg::RemoveFilter(key))}></button>
+                        </span>
+                    }).collect::<Html>()
+                }
+                <a href={String::from("javascript:void(0)")} class="tag is-light" onclick={ctx.link().callback(|_|UserListMsg::ClearFilters)}>{"clear all"}</a>
+                </div>
+            }
         </div>
         <div class="table-container">
             {
@@ -209,21 +665,22 @@ impl Component for UserList {
             <table class="table is-bordered is-striped is-narrow is-hoverable">
             <thead>
                 <tr>
-                <th><abbr title="Type">{"Type"}</abbr></th>
-                <th><abbr title="Email">{"Email"}</abbr></th>
-                <th><abbr title="Name">{"Name"}</abbr></th>
-                <th><abbr title="Mobile">{"Mobile"}</abbr></th>
-                <th><abbr title="Laston">{"Laston"}</abbr></th>
-                // todo: sort
-                <th><abbr title="Created_at"><a href="javascript:void(0)">{"Created_at  "}<i class="fa-solid fa-arrow-down"></i></a></abbr></th>
-                <th><abbr title="Updated_at">{"Updated_at"}</abbr></th>
-                <th><abbr title="Status">{"Status"}</abbr></th>
+                <th><input type="checkbox" checked={all_selected} onclick={ctx.link().callback(|_|UserListMsg::ToggleAll)}/></th>
+                { self.sort_header(ctx, SortField::Type, "Type") }
+                { self.sort_header(ctx, SortField::Email, "Email") }
+                { self.sort_header(ctx, SortField::Name, "Name") }
+                <th><abbr title="Roles">{"Roles"}</abbr></th>
+                { self.sort_header(ctx, SortField::Mobile, "Mobile") }
+                { self.sort_header(ctx, SortField::Laston, "Laston") }
+                { self.sort_header(ctx, SortField::CreatedAt, "Created_at") }
+                { self.sort_header(ctx, SortField::UpdatedAt, "Updated_at") }
+                { self.sort_header(ctx, SortField::Status, "Status") }
                 </tr>
             </thead>
             <tbody>
             {
                 data.iter().map(|x| html!{
-                    <UserListItem is_selected = {selected_id.is_some() && selected_id.clone().unwrap()==x.user.id} onselect={ctx.link().callback(|user|{UserListMsg::OnSelect(user)})}  value={x.clone()} />
+                    <UserListItem roles = {self.user_roles.get(&x.user.id).cloned().unwrap_or_default()} is_selected = {is_selected(x.user.id)} onselect={ctx.link().callback(|user|{UserListMsg::OnSelect(user)})} oncheck={ctx.link().callback(|(user, shift)|{UserListMsg::OnCheck(user, shift)})}  value={x.clone()} />
                 }).collect::<Html>()
             }
             </tbody>
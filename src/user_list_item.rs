@@ -1,3 +1,4 @@
+use crate::role_list::Role;
 use crate::util::common;
 use user_cli::models::{SearchedUser, User};
 use yew::prelude::*;
@@ -9,12 +10,18 @@ pub struct UserListItem;
 pub struct UserListItemProps {
     pub value: SearchedUser,
     #[prop_or_default]
+    pub roles: Vec<Role>,
+    #[prop_or_default]
     pub is_selected: bool,
     pub onselect: Callback<User>,
+    // (user, shift key pressed)
+    #[prop_or_default]
+    pub oncheck: Callback<(User, bool)>,
 }
 
 pub enum UserListItemMsg {
     Select,
+    Check(bool),
 }
 
 impl Component for UserListItem {
@@ -32,6 +39,11 @@ impl Component for UserListItem {
                 ctx.props().onselect.emit(*val.user);
                 false
             }
+            UserListItemMsg::Check(shift) => {
+                let val = ctx.props().value.clone();
+                ctx.props().oncheck.emit((*val.user, shift));
+                false
+            }
         }
     }
 
@@ -43,9 +55,24 @@ impl Component for UserListItem {
         html! {
             <tr class = {if is_selected {"is-selected"} else {""}}
              onclick = {ctx.link().callback(|_| UserListItemMsg::Select)} >
+                <td onclick = {ctx.link().callback(|e: MouseEvent| {
+                    e.stop_propagation();
+                    UserListItemMsg::Check(e.shift_key())
+                })}>
+                    <input type="checkbox" checked={is_selected}/>
+                </td>
                 {common::create_html("td",formatter.r#type.as_str())}
                 {common::create_html("td",formatter.email.as_str())}
                 {common::create_html("td",formatter.name.as_str())}
+                <td>
+                    <div class="tags">
+                    {
+                        ctx.props().roles.iter().map(|x|html!{
+                            <span class="tag is-info is-light">{x.name.clone()}</span>
+                        }).collect::<Html>()
+                    }
+                    </div>
+                </td>
                 {common::create_html("td",formatter.mobile.as_str())}
                 {common::create_html("td",formatter.laston.as_str())}
                 {common::create_html("td",formatter.created_at.as_str())}
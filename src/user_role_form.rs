@@ -0,0 +1,246 @@
+use crate::role_list::Role;
+use crate::util::request;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::component::message_list::{MessageList, MessageListValue, MessageOperate};
+use std::sync::{Arc, Mutex};
+use user_cli::models::User;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew::Properties;
+
+pub struct UserRoleForm {
+    messages: Arc<Mutex<MessageListValue>>,
+    roles: Vec<Role>,
+    // what the selected users hold when the form opens, and what the user made of it
+    initial: HashMap<i64, RoleState>,
+    states: HashMap<i64, RoleState>,
+    // the checkboxes, to show the mixed state (a DOM property, not an attribute)
+    refs: HashMap<i64, NodeRef>,
+    loaded: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RoleState {
+    Checked,
+    Unchecked,
+    /// held by some of the selected users, left as it is on save
+    Mixed,
+}
+
+pub enum UserRoleFormMsg {
+    Close,
+    Load,
+    HandleLoadSuccess(Vec<Role>),
+    HandleLoadFail(Box<dyn std::error::Error>),
+    Toggle(i64),
+    Save,
+    SaveSuccess,
+    SaveError(Box<dyn std::error::Error>),
+}
+
+/// Roles are added and removed, never replaced, so a role held by only some users stays with them.
+#[derive(Serialize)]
+pub struct AssignRolesReq {
+    pub user_ids: Vec<i64>,
+    pub add_role_ids: Vec<i64>,
+    pub remove_role_ids: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct UserRoleFormProps {
+    pub users: Vec<User>,
+    /// held by every one of `users`
+    #[prop_or_default]
+    pub assigned: Vec<Role>,
+    /// held by some of `users` only
+    #[prop_or_default]
+    pub partial: Vec<Role>,
+    #[prop_or_default]
+    pub update: Callback<()>,
+    pub closed: Rc<RefCell<bool>>,
+}
+
+impl UserRoleForm {
+    fn initial_state(&self, id: i64) -> RoleState {
+        self.initial
+            .get(&id)
+            .copied()
+            .unwrap_or(RoleState::Unchecked)
+    }
+
+    fn state(&self, id: i64) -> RoleState {
+        self.states
+            .get(&id)
+            .copied()
+            .unwrap_or(RoleState::Unchecked)
+    }
+}
+
+impl Component for UserRoleForm {
+    type Message = UserRoleFormMsg;
+
+    type Properties = UserRoleFormProps;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            messages: Default::default(),
+            roles: Default::default(),
+            initial: Default::default(),
+            states: Default::default(),
+            refs: Default::default(),
+            loaded: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            UserRoleFormMsg::Close => {
+                *ctx.props().closed.borrow_mut() = true;
+                self.loaded = false;
+                true
+            }
+            UserRoleFormMsg::Load => {
+                self.loaded = true;
+                self.initial = ctx
+                    .props()
+                    .assigned
+                    .iter()
+                    .map(|x| (x.id, RoleState::Checked))
+                    .chain(ctx.props().partial.iter().map(|x| (x.id, RoleState::Mixed)))
+                    .collect();
+                self.states = self.initial.clone();
+                ctx.link().send_future(async move {
+                    match request::get::<Vec<Role>, Vec<(&str, &str)>, _>(
+                        request::Host::ApiBase,
+                        "/role/all",
+                        None,
+                    )
+                    .await
+                    {
+                        Ok(res) => UserRoleFormMsg::HandleLoadSuccess(res.data.unwrap_or_default()),
+                        Err(err) => UserRoleFormMsg::HandleLoadFail(Box::new(err)),
+                    }
+                });
+                false
+            }
+            UserRoleFormMsg::HandleLoadSuccess(roles) => {
+                self.refs = roles.iter().map(|x| (x.id, NodeRef::default())).collect();
+                self.roles = roles;
+                true
+            }
+            UserRoleFormMsg::HandleLoadFail(_err) => {
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+            UserRoleFormMsg::Toggle(id) => {
+                let next = match (self.state(id), self.initial_state(id)) {
+                    (RoleState::Unchecked, RoleState::Mixed) => RoleState::Mixed,
+                    (RoleState::Unchecked, _) => RoleState::Checked,
+                    (RoleState::Checked, _) => RoleState::Unchecked,
+                    (RoleState::Mixed, _) => RoleState::Checked,
+                };
+                self.states.insert(id, next);
+                true
+            }
+            UserRoleFormMsg::Save => {
+                let changed = |state: RoleState| {
+                    self.roles
+                        .iter()
+                        .map(|x| x.id)
+                        .filter(|&id| self.state(id) == state && self.initial_state(id) != state)
+                        .collect::<Vec<i64>>()
+                };
+                let req = AssignRolesReq {
+                    user_ids: ctx.props().users.iter().map(|x| x.id).collect(),
+                    add_role_ids: changed(RoleState::Checked),
+                    remove_role_ids: changed(RoleState::Unchecked),
+                };
+                if req.add_role_ids.is_empty() && req.remove_role_ids.is_empty() {
+                    ctx.link().send_message(UserRoleFormMsg::Close);
+                    return false;
+                }
+                ctx.link().send_future(async move {
+                    match request::put::<u64, _>(request::Host::ApiBase, "/user/roles", &req).await
+                    {
+                        Ok(_) => UserRoleFormMsg::SaveSuccess,
+                        Err(err) => UserRoleFormMsg::SaveError(Box::new(err)),
+                    }
+                });
+                false
+            }
+            UserRoleFormMsg::SaveSuccess => {
+                ctx.props().update.emit(());
+                ctx.link().send_message(UserRoleFormMsg::Close);
+                false
+            }
+            UserRoleFormMsg::SaveError(_err) => {
+                self.messages.request_failed(_err.as_ref());
+                true
+            }
+        }
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        if !*ctx.props().closed.borrow() && !self.loaded {
+            ctx.link().send_message(UserRoleFormMsg::Load);
+        }
+        // a click flips the DOM before the state does, set both from the state
+        for (id, node) in self.refs.iter() {
+            if let Some(input) = node.cast::<HtmlInputElement>() {
+                input.set_checked(self.state(*id) == RoleState::Checked);
+                input.set_indeterminate(self.state(*id) == RoleState::Mixed);
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if ctx.props().closed.borrow().clone() {
+            return html! {};
+        }
+        let users = &ctx.props().users;
+        let title = match users.as_slice() {
+            [user] => format!("Assign roles: {}", user.email),
+            _ => format!("Assign roles: {} users", users.len()),
+        };
+        html! {
+            <div class="modal is-active">
+                <div class="modal-background"></div>
+                <div class="modal-card">
+                    <MessageList value={self.messages.clone()}/>
+                    <header class="modal-card-head">
+                    <p class="modal-card-title">{title}</p>
+                    <button class="delete" aria-label="close" onclick={ctx.link().callback(|_|UserRoleFormMsg::Close)}></button>
+                    </header>
+                    <section class="modal-card-body">
+                    {
+                        if self.roles.is_empty() {
+                            html!{ <p>{"no roles available"}</p> }
+                        } else {
+                            self.roles.iter().map(|role| {
+                                let id = role.id;
+                                html!{
+                                    <div class="field">
+                                        <label class="checkbox">
+                                        <input type="checkbox" ref={self.refs.get(&id).cloned().unwrap_or_default()} checked={self.state(id) == RoleState::Checked} onchange={ctx.link().callback(move|_|UserRoleFormMsg::Toggle(id))}/>
+                                        {format!(" {}", role.name)}
+                                        </label>
+                                        <p class="help">{role.description.clone().unwrap_or_default()}</p>
+                                    </div>
+                                }
+                            }).collect::<Html>()
+                        }
+                    }
+                    </section>
+                    <footer class="modal-card-foot">
+                    <button class="button is-success"  onclick={ctx.link().callback(|_|UserRoleFormMsg::Save)}>{"Save changes"}</button>
+                    <button class="button" onclick={ctx.link().callback(|_|UserRoleFormMsg::Close)} >{"Cancel"}</button>
+                    </footer>
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use crate::component::pager::Page;
+use crate::util::common::BasicResult;
+use crate::util::request;
+use serde::{Deserialize, Serialize};
+use user_cli::models::SearchedUser;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum UserStatus {
+    Available,
+    Disabled,
+}
+
+impl UserStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Available => "Available",
+            UserStatus::Disabled => "Disabled",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Available" => Some(UserStatus::Available),
+            "Disabled" => Some(UserStatus::Disabled),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+pub enum UserType {
+    Admin,
+    Normal,
+}
+
+impl UserType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserType::Admin => "Admin",
+            UserType::Normal => "Normal",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Admin" => Some(UserType::Admin),
+            "Normal" => Some(UserType::Normal),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    Type,
+    Email,
+    Name,
+    Mobile,
+    Laston,
+    CreatedAt,
+    UpdatedAt,
+    Status,
+}
+
+impl SortField {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortField::Type => "type",
+            SortField::Email => "email",
+            SortField::Name => "name",
+            SortField::Mobile => "mobile",
+            SortField::Laston => "laston",
+            SortField::CreatedAt => "created_at",
+            SortField::UpdatedAt => "updated_at",
+            SortField::Status => "status",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Sort {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    /// none -> asc -> desc -> none, another field always starts from asc
+    pub fn toggle(current: Option<Sort>, field: SortField) -> Option<Sort> {
+        match current {
+            Some(Sort {
+                field: f,
+                order: SortOrder::Asc,
+            }) if f == field => Some(Sort {
+                field,
+                order: SortOrder::Desc,
+            }),
+            Some(Sort {
+                field: f,
+                order: SortOrder::Desc,
+            }) if f == field => None,
+            _ => Some(Sort {
+                field,
+                order: SortOrder::Asc,
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FilterKey {
+    Type,
+    Status,
+    CreatedFrom,
+    CreatedTo,
+    LastonFrom,
+    LastonTo,
+    HasMobile,
+    HasName,
+}
+
+/// structured filters combined with the free-text key_word, dates are `yyyy-mm-dd`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct UserFilter {
+    pub r#type: Option<UserType>,
+    pub status: Option<UserStatus>,
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub laston_from: Option<String>,
+    pub laston_to: Option<String>,
+    pub has_mobile: Option<bool>,
+    pub has_name: Option<bool>,
+}
+
+impl UserFilter {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn clear(&mut self, key: FilterKey) {
+        match key {
+            FilterKey::Type => self.r#type = None,
+            FilterKey::Status => self.status = None,
+            FilterKey::CreatedFrom => self.created_from = None,
+            FilterKey::CreatedTo => self.created_to = None,
+            FilterKey::LastonFrom => self.laston_from = None,
+            FilterKey::LastonTo => self.laston_to = None,
+            FilterKey::HasMobile => self.has_mobile = None,
+            FilterKey::HasName => self.has_name = None,
+        }
+    }
+
+    /// active filters as removable tags
+    pub fn tags(&self) -> Vec<(FilterKey, String)> {
+        let set = |v: bool| if v { "set" } else { "not set" };
+        let mut res = vec![];
+        if let Some(v) = self.r#type {
+            res.push((FilterKey::Type, format!("type: {}", v.as_str())));
+        }
+        if let Some(v) = self.status {
+            res.push((FilterKey::Status, format!("status: {}", v.as_str())));
+        }
+        if let Some(v) = &self.created_from {
+            res.push((FilterKey::CreatedFrom, format!("created from: {}", v)));
+        }
+        if let Some(v) = &self.created_to {
+            res.push((FilterKey::CreatedTo, format!("created to: {}", v)));
+        }
+        if let Some(v) = &self.laston_from {
+            res.push((FilterKey::LastonFrom, format!("laston from: {}", v)));
+        }
+        if let Some(v) = &self.laston_to {
+            res.push((FilterKey::LastonTo, format!("laston to: {}", v)));
+        }
+        if let Some(v) = self.has_mobile {
+            res.push((FilterKey::HasMobile, format!("mobile: {}", set(v))));
+        }
+        if let Some(v) = self.has_name {
+            res.push((FilterKey::HasName, format!("name: {}", set(v))));
+        }
+        res
+    }
+
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![];
+        if let Some(v) = self.r#type {
+            params.push(("type", v.as_str().to_string()));
+        }
+        if let Some(v) = self.status {
+            params.push(("status", v.as_str().to_string()));
+        }
+        if let Some(v) = &self.created_from {
+            params.push(("created_from", v.clone()));
+        }
+        if let Some(v) = &self.created_to {
+            params.push(("created_to", v.clone()));
+        }
+        if let Some(v) = &self.laston_from {
+            params.push(("laston_from", v.clone()));
+        }
+        if let Some(v) = &self.laston_to {
+            params.push(("laston_to", v.clone()));
+        }
+        if let Some(v) = self.has_mobile {
+            params.push(("has_mobile", v.to_string()));
+        }
+        if let Some(v) = self.has_name {
+            params.push(("has_name", v.to_string()));
+        }
+        params
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct UserSearchReq {
+    pub key_word: String,
+    pub index: usize,
+    pub size: usize,
+    pub sort: Option<Sort>,
+    pub filter: UserFilter,
+}
+
+impl UserSearchReq {
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = vec![
+            ("key_word", self.key_word.clone()),
+            ("index", self.index.to_string()),
+            ("size", self.size.to_string()),
+        ];
+        if let Some(sort) = &self.sort {
+            params.push(("sort_field", sort.field.as_str().to_string()));
+            params.push(("sort_order", sort.order.as_str().to_string()));
+        }
+        params.extend(self.filter.to_params());
+        params
+    }
+}
+
+/// list state kept in the `/main/user` query string, flat because serde_urlencoded can not flatten
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct UserListQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_word: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_field: Option<SortField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<SortOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<UserType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<UserStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub laston_from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub laston_to: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_mobile: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_name: Option<bool>,
+}
+
+impl UserListQuery {
+    pub fn new(
+        key_word: Option<&str>,
+        page: &Page,
+        sort: Option<Sort>,
+        filter: &UserFilter,
+    ) -> Self {
+        let default_page = Page::default();
+        Self {
+            index: Some(page.index).filter(|x| *x != default_page.index),
+            size: Some(page.size).filter(|x| *x != default_page.size),
+            key_word: key_word.filter(|x| !x.is_empty()).map(|x| x.to_string()),
+            sort_field: sort.map(|x| x.field),
+            sort_order: sort.map(|x| x.order),
+            r#type: filter.r#type,
+            status: filter.status,
+            created_from: filter.created_from.clone(),
+            created_to: filter.created_to.clone(),
+            laston_from: filter.laston_from.clone(),
+            laston_to: filter.laston_to.clone(),
+            has_mobile: filter.has_mobile,
+            has_name: filter.has_name,
+        }
+    }
+
+    pub fn sort(&self) -> Option<Sort> {
+        match (self.sort_field, self.sort_order) {
+            (Some(field), Some(order)) => Some(Sort { field, order }),
+            _ => None,
+        }
+    }
+
+    pub fn filter(&self) -> UserFilter {
+        UserFilter {
+            r#type: self.r#type,
+            status: self.status,
+            created_from: self.created_from.clone(),
+            created_to: self.created_to.clone(),
+            laston_from: self.laston_from.clone(),
+            laston_to: self.laston_to.clone(),
+            has_mobile: self.has_mobile,
+            has_name: self.has_name,
+        }
+    }
+
+    /// apply index and size on top of the current page, total is kept
+    pub fn apply_page(&self, page: &mut Page) {
+        let default_page = Page::default();
+        page.index = self.index.filter(|x| *x > 0).unwrap_or(default_page.index);
+        page.size = self.size.filter(|x| *x > 0).unwrap_or(default_page.size);
+    }
+}
+
+// the generated user_controller_api::search only takes key_word/index/size,
+// so sort and filters go through util::request against the same endpoint
+pub async fn search(req: &UserSearchReq) -> BasicResult<(Vec<SearchedUser>, usize)> {
+    let res = request::get::<Vec<SearchedUser>, _, _>(
+        request::Host::ApiBase,
+        "/user/search",
+        Some(req.to_params()),
+    )
+    .await?;
+    Ok((res.data.unwrap_or_default(), res.total.unwrap_or_default()))
+}
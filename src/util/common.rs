@@ -1,14 +1,25 @@
+use crate::util::config;
 use crate::util::error::ErrorKind;
 use crate::util::error::ToError;
+use crate::util::interceptor::{self, RequestInfo};
+use crate::util::permission;
 use crate::util::request;
+use crate::Route;
 use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
 use serde_json;
-use user_cli::apis::configuration::{ApiKey, Configuration};
+use user_cli::apis::configuration::Configuration;
 use yew::virtual_dom::VNode;
+use yew_router::history::{BrowserHistory, History};
+use yew_router::Routable;
 
 pub type BasicResult<T, E = ErrorKind> = Result<T, E>;
 
+const REMEMBER_ME_KEY: &str = "remember_me";
+const STORAGE_VERSION_KEY: &str = "storage_version";
+const STORAGE_VERSION: u32 = 1;
+
 pub fn validate_email(email: &str) -> BasicResult<()> {
     if email.is_empty() {
         return Err("please type in email".to_validation_error());
@@ -22,10 +33,12 @@ pub fn validate_email(email: &str) -> BasicResult<()> {
 
 pub async fn validate_exist_email(email: &str) -> BasicResult<()> {
     validate_email(email)?;
-    request::get::<(), Vec<(&str, &str)>, _>(
+    // checked while typing, a retry would answer a value that is already gone
+    request::get_with::<(), Vec<(&str, &str)>, _>(
         request::Host::ApiBase,
         &format!("/validate_exist_email/{}", email),
         None,
+        request::RequestOptions::no_retry(),
     )
     .await?;
 
@@ -34,10 +47,11 @@ pub async fn validate_exist_email(email: &str) -> BasicResult<()> {
 
 pub async fn validate_not_exist_email(email: &str) -> BasicResult<()> {
     validate_email(email)?;
-    request::get::<(), Vec<(&str, &str)>, _>(
+    request::get_with::<(), Vec<(&str, &str)>, _>(
         request::Host::ApiBase,
         &format!("/validate_not_exist_email/{}", email),
         None,
+        request::RequestOptions::no_retry(),
     )
     .await?;
     Ok(())
@@ -84,41 +98,80 @@ pub struct CurrentUser {
     pub created_at: String,
     pub updated_at: Option<String>,
     pub expire_at: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
 pub fn get_token() -> BasicResult<String> {
-    let str = get_local_storage("token")
-        .ok_or(ErrorKind::OtherError(String::from("get token failed")))?;
+    let str =
+        get_session_item("token").ok_or(ErrorKind::OtherError(String::from("get token failed")))?;
     Ok(str)
 }
 
-pub fn get_cli_config_without_token() -> BasicResult<Configuration> {
+pub fn set_token(token: &str) {
+    set_session_item("token", token);
+}
+
+// the headers of the request interceptors go in as the client's default headers,
+// so every call made with one configuration shares them, the request id included
+fn build_cli_config(anonymous: bool) -> BasicResult<Configuration> {
     let mut ret = Configuration::default();
-    ret.base_path = "http://localhost:8881".to_string();
+    ret.base_path = config::get().api_base();
+    let mut info = RequestInfo::new(&ret.base_path, anonymous);
+    interceptor::intercept_request(&mut info);
+    let mut headers = HeaderMap::new();
+    for (k, v) in info.headers {
+        let name = HeaderName::from_bytes(k.as_bytes())
+            .map_err(|e| ErrorKind::OtherError(e.to_string()))?;
+        let value = HeaderValue::from_str(&v).map_err(|e| ErrorKind::OtherError(e.to_string()))?;
+        headers.insert(name, value);
+    }
+    ret.client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .map_err(|e| ErrorKind::OtherError(e.to_string()))?;
     Ok(ret)
 }
 
+/// Make one per `user_cli` call: its headers, the request id among them, are fixed when it is built.
+pub fn get_cli_config_without_token() -> BasicResult<Configuration> {
+    build_cli_config(true)
+}
+
+/// Make one per `user_cli` call, like `get_cli_config_without_token`.
 pub fn get_cli_config() -> BasicResult<Configuration> {
-    let mut ret = Configuration::default();
-    ret.base_path = "http://localhost:8881".to_string();
-    ret.api_key = Some(ApiKey {
-        prefix: Some("Bearer".to_string()),
-        key: get_token()?,
-    });
-    Ok(ret)
+    // credentials come from the auth interceptor, but a call that needs them fails early without
+    get_token()?;
+    build_cli_config(false)
 }
 
 pub fn get_current_user() -> BasicResult<CurrentUser> {
-    let str = get_local_storage("current_user").ok_or(ErrorKind::OtherError(String::from(
+    let str = get_session_item("current_user").ok_or(ErrorKind::OtherError(String::from(
         "current user str is null",
     )))?;
     let res = serde_json::from_str::<CurrentUser>(&str)?;
     Ok(res)
 }
 
+/// reload the logged in user and its permissions with the current token and store them
+pub async fn refresh_current_user() -> BasicResult<CurrentUser> {
+    let res = request::get::<CurrentUser, Vec<(&str, &str)>, _>(
+        request::Host::ApiBase,
+        "/user/get_current_user",
+        None,
+    )
+    .await?;
+    let mut user = res
+        .data
+        .ok_or(ErrorKind::OtherError(String::from("current user is null")))?;
+    user.permissions = permission::fetch().await?;
+    set_session_item("current_user", &serde_json::to_string(&user)?);
+    Ok(user)
+}
+
 pub fn delete_current_user() -> BasicResult<()> {
-    del_local_storage("token");
-    del_local_storage("current_user");
+    del_session_item("token");
+    del_session_item("current_user");
     del_local_storage("selected_navbar_name");
     del_local_storage("selected_navbar_parent_name");
     Ok(())
@@ -139,12 +192,143 @@ pub fn del_local_storage(key: &str) {
     local_storage.delete(key).unwrap()
 }
 
-pub fn redirect(path: &str) {
-    web_sys::window()
+pub fn set_session_storage(key: &str, value: &str) {
+    let session_storage = web_sys::window()
+        .unwrap()
+        .session_storage()
+        .unwrap()
+        .unwrap();
+    session_storage.set_item(key, value).unwrap();
+}
+
+pub fn get_session_storage(key: &str) -> Option<String> {
+    let session_storage = web_sys::window()
+        .unwrap()
+        .session_storage()
         .unwrap()
-        .location()
-        .set_pathname(path)
         .unwrap();
+    session_storage.get_item(key).unwrap()
+}
+
+pub fn del_session_storage(key: &str) {
+    let session_storage = web_sys::window()
+        .unwrap()
+        .session_storage()
+        .unwrap()
+        .unwrap();
+    session_storage.delete(key).unwrap()
+}
+
+pub fn is_remember_me() -> bool {
+    get_local_storage(REMEMBER_ME_KEY).is_some_and(|x| x == "true")
+}
+
+/// "remember me" keeps the session in localStorage across browser restarts,
+/// otherwise it lives in sessionStorage and is gone with the tab
+pub fn set_remember_me(remember: bool) {
+    if remember {
+        set_local_storage(REMEMBER_ME_KEY, "true");
+    } else {
+        del_local_storage(REMEMBER_ME_KEY);
+    }
+}
+
+// session data (token, current user) goes to the storage picked by "remember me"
+pub fn set_session_item(key: &str, value: &str) {
+    if is_remember_me() {
+        del_session_storage(key);
+        set_local_storage(key, value);
+    } else {
+        del_local_storage(key);
+        set_session_storage(key, value);
+    }
+}
+
+pub fn get_session_item(key: &str) -> Option<String> {
+    get_session_storage(key).or_else(|| get_local_storage(key))
+}
+
+pub fn del_session_item(key: &str) {
+    del_session_storage(key);
+    del_local_storage(key);
+}
+
+/// one-time clean up of what older versions left in localStorage
+pub fn migrate_storage() {
+    let version = get_local_storage(STORAGE_VERSION_KEY)
+        .and_then(|x| x.parse::<u32>().ok())
+        .unwrap_or_default();
+    if version >= STORAGE_VERSION {
+        return;
+    }
+    // plaintext password saved by the login, register and forget password pages
+    del_local_storage("pwd");
+    set_local_storage(STORAGE_VERSION_KEY, &STORAGE_VERSION.to_string());
+}
+
+/// in-app navigation to a path (with query) where no component scope is at hand,
+/// pushes onto the same browser history the router's `Navigator` wraps, so nothing reloads
+pub fn redirect(path: &str) {
+    BrowserHistory::new().push(path);
+}
+
+/// `?next=` carried by the login redirects
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+pub struct NextQuery {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+}
+
+/// path, query and hash of the current page
+pub fn current_location() -> String {
+    let location = web_sys::window().unwrap().location();
+    format!(
+        "{}{}{}",
+        location.pathname().unwrap_or_default(),
+        location.search().unwrap_or_default(),
+        location.hash().unwrap_or_default()
+    )
+}
+
+/// accept only a same-origin route of this app, auth and error pages excluded,
+/// so that `?next=` can not send the user elsewhere or back in a loop
+pub fn validate_next(next: &str) -> Option<String> {
+    if !next.starts_with('/') || next.starts_with("//") || next.starts_with("/\\") {
+        return None;
+    }
+    let origin = web_sys::window().unwrap().location().origin().ok()?;
+    let url = web_sys::Url::new_with_base(next, &origin).ok()?;
+    if url.origin() != origin {
+        return None;
+    }
+    match Route::recognize(&url.pathname()) {
+        None
+        | Some(Route::PageNotFound)
+        | Some(Route::Login)
+        | Some(Route::Register)
+        | Some(Route::ForgetPwd)
+        | Some(Route::Unauthorized)
+        | Some(Route::Forbidden)
+        | Some(Route::NotFound) => None,
+        Some(_) => Some(format!("{}{}{}", url.pathname(), url.search(), url.hash())),
+    }
+}
+
+/// `path?next=...`, or just `path` when next is not a valid destination
+pub fn with_next(path: &str, next: &str) -> String {
+    match validate_next(next) {
+        Some(next) => {
+            let params = web_sys::UrlSearchParams::new().unwrap();
+            params.append("next", &next);
+            format!("{}?{}", path, String::from(params.to_string()))
+        }
+        None => path.to_string(),
+    }
+}
+
+/// leave the current page for `path`, remembering it as the destination after login
+pub fn redirect_with_next(path: &str) {
+    redirect(&with_next(path, &current_location()));
 }
 
 pub fn create_html(tag: &str, inner_html: &str) -> VNode {
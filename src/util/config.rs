@@ -0,0 +1,145 @@
+use gloo_net::http::Request;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::sync::RwLock;
+
+const CONFIG_PATH: &str = "/config.json";
+
+// compile-time fallback, override with `API_BASE=https://... trunk build`
+const DEFAULT_API_BASE: &str = match option_env!("API_BASE") {
+    Some(v) => v,
+    None => "http://localhost:8881",
+};
+
+lazy_static! {
+    static ref CONFIG: RwLock<AppConfig> = RwLock::new(AppConfig::default());
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AppConfig {
+    pub api_base: String,
+    // derived from api_base when absent: http -> ws, https -> wss
+    pub ws_base: Option<String>,
+    pub auth_header: AuthHeader,
+    pub features: Features,
+    pub retry: RetryPolicy,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Features {
+    pub chat: bool,
+    pub register: bool,
+    // backend exposes /user/refresh_token, otherwise warn before the session expires
+    pub session_refresh: bool,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            api_base: DEFAULT_API_BASE.to_string(),
+            ws_base: None,
+            auth_header: Default::default(),
+            features: Default::default(),
+            retry: Default::default(),
+        }
+    }
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Self {
+            chat: true,
+            register: true,
+            session_refresh: false,
+        }
+    }
+}
+
+/// header carrying the session token
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthHeader {
+    // `token: Bearer ...`, what the backend reads by default
+    Token,
+    // `Authorization: Bearer ...`
+    Authorization,
+}
+
+impl Default for AuthHeader {
+    fn default() -> Self {
+        AuthHeader::Token
+    }
+}
+
+/// retry of idempotent requests on network errors and 502/503/504
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RetryPolicy {
+    // including the first one, 1 turns retry off
+    pub max_attempts: u32,
+    pub base_delay_millis: u32,
+    pub max_delay_millis: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_millis: 500,
+            max_delay_millis: 5_000,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn api_base(&self) -> String {
+        self.api_base.trim_end_matches('/').to_string()
+    }
+
+    pub fn ws_base(&self) -> String {
+        match &self.ws_base {
+            Some(v) => v.trim_end_matches('/').to_string(),
+            None => {
+                let api_base = self.api_base();
+                if let Some(rest) = api_base.strip_prefix("https://") {
+                    format!("wss://{}", rest)
+                } else if let Some(rest) = api_base.strip_prefix("http://") {
+                    format!("ws://{}", rest)
+                } else {
+                    api_base
+                }
+            }
+        }
+    }
+}
+
+/// fetch `/config.json` next to index.html, keep the compile-time fallback on any failure
+pub async fn load() {
+    let config = match Request::get(CONFIG_PATH).send().await {
+        Ok(res) if res.ok() => match res.json::<AppConfig>().await {
+            Ok(v) => Some(v),
+            Err(err) => {
+                log::warn!("parse {} error: {}", CONFIG_PATH, err);
+                None
+            }
+        },
+        Ok(res) => {
+            log::warn!("load {} failed, status: {}", CONFIG_PATH, res.status());
+            None
+        }
+        Err(err) => {
+            log::warn!("load {} error: {}", CONFIG_PATH, err);
+            None
+        }
+    };
+
+    if let Some(v) = config {
+        *CONFIG.write().unwrap() = v;
+    }
+}
+
+pub fn get() -> AppConfig {
+    CONFIG.read().unwrap().clone()
+}
@@ -0,0 +1,61 @@
+use gloo::timers::callback::Timeout;
+
+pub const DEFAULT_DELAY_MILLIS: u32 = 300;
+
+/// Tags each request with an increasing id, so a response whose id is no longer
+/// the current one belongs to a superseded request and can be dropped.
+#[derive(Default, Debug)]
+pub struct Generation {
+    current: u64,
+}
+
+impl Generation {
+    /// start a new request, everything issued before becomes stale
+    pub fn next(&mut self) -> u64 {
+        self.current += 1;
+        self.current
+    }
+
+    pub fn current(&self) -> u64 {
+        self.current
+    }
+
+    pub fn is_current(&self, id: u64) -> bool {
+        self.current == id
+    }
+}
+
+/// Delays a call until the input settles, each new call cancels the pending one.
+pub struct Debounce {
+    delay_millis: u32,
+    pending: Option<Timeout>,
+}
+
+impl Default for Debounce {
+    fn default() -> Self {
+        Self::new(DEFAULT_DELAY_MILLIS)
+    }
+}
+
+impl Debounce {
+    pub fn new(delay_millis: u32) -> Self {
+        Self {
+            delay_millis,
+            pending: None,
+        }
+    }
+
+    pub fn call<F>(&mut self, f: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        // dropping the previous Timeout cancels it
+        self.pending = Some(Timeout::new(self.delay_millis, f));
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(timeout) = self.pending.take() {
+            timeout.cancel();
+        }
+    }
+}
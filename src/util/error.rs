@@ -1,5 +1,10 @@
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fmt::Display;
 
+// status the backend uses for messages meant to be shown to the user as is
+pub const HINT_STATUS: u16 = 452;
+
 #[derive(Debug, Clone)]
 pub enum ErrorKind {
     BasicError(String),
@@ -7,18 +12,85 @@ pub enum ErrorKind {
     ServerError(String),
     Hint(String),
     OtherError(String),
+    // the request never got a response
+    NetworkError(String),
+    // the backend answered with a 4xx/5xx
+    ApiError(ApiError),
+}
+
+/// A failed response from the backend, whichever client sent the request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub msg: String,
+    // field name -> message, for forms to show next to the input
+    pub fields: HashMap<String, String>,
+    // a response interceptor already acted on it (left the page), nothing to show
+    pub redirected: bool,
+}
+
+// `{"msg": "...", "errors": {"email": "..."}}`, both optional
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ErrorBody {
+    msg: Option<String>,
+    errors: HashMap<String, String>,
+}
+
+impl ApiError {
+    pub fn from_body(status: u16, body: &str) -> Self {
+        let body = serde_json::from_str::<ErrorBody>(body).unwrap_or_default();
+        Self {
+            status,
+            msg: body
+                .msg
+                .filter(|x| !x.is_empty())
+                .unwrap_or(format!("request failed with status {}", status)),
+            fields: body.errors,
+            redirected: false,
+        }
+    }
+}
+
+impl ErrorKind {
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            ErrorKind::ApiError(e) => Some(e.status),
+            _ => None,
+        }
+    }
+
+    pub fn is_redirected(&self) -> bool {
+        matches!(self, ErrorKind::ApiError(e) if e.redirected)
+    }
+
+    pub fn is_hint(&self) -> bool {
+        match self {
+            ErrorKind::Hint(_) => true,
+            ErrorKind::ApiError(e) => e.status == HINT_STATUS,
+            _ => false,
+        }
+    }
+
+    /// server message for one form field, if the backend sent one
+    pub fn field(&self, name: &str) -> Option<&str> {
+        match self {
+            ErrorKind::ApiError(e) => e.fields.get(name).map(|x| x.as_str()),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> Display for ErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ErrorKind::BasicError(msg) => f.write_str(msg),
-            ErrorKind::ValidationError(msg) => {
-                f.write_fmt(format_args!("{}", msg))
-            }
+            ErrorKind::ValidationError(msg) => f.write_fmt(format_args!("{}", msg)),
             ErrorKind::ServerError(msg) => f.write_str(msg),
             ErrorKind::Hint(msg) => f.write_str(msg),
             ErrorKind::OtherError(msg) => f.write_fmt(format_args!("other error: {}", msg)),
+            ErrorKind::NetworkError(msg) => f.write_fmt(format_args!("network error: {}", msg)),
+            ErrorKind::ApiError(e) => f.write_str(&e.msg),
         }
     }
 }
@@ -39,7 +111,27 @@ impl From<serde_json::Error> for ErrorKind {
 
 impl From<gloo_net::Error> for ErrorKind {
     fn from(err: gloo_net::Error) -> Self {
-        ErrorKind::OtherError(err.to_string())
+        match err {
+            // fetch itself rejected: offline, dns, cors...
+            gloo_net::Error::JsError(e) => ErrorKind::NetworkError(e.to_string()),
+            err => ErrorKind::OtherError(err.to_string()),
+        }
+    }
+}
+
+impl<T> From<user_cli::apis::Error<T>> for ErrorKind
+where
+    T: std::fmt::Debug,
+{
+    fn from(err: user_cli::apis::Error<T>) -> Self {
+        match err {
+            // the typed entity only covers documented statuses, the raw content has them all
+            user_cli::apis::Error::ResponseError(res) => {
+                ErrorKind::ApiError(ApiError::from_body(res.status.as_u16(), &res.content))
+            }
+            user_cli::apis::Error::Reqwest(e) => ErrorKind::NetworkError(e.to_string()),
+            err => ErrorKind::OtherError(err.to_string()),
+        }
     }
 }
 
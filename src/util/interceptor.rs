@@ -0,0 +1,239 @@
+use crate::util::common;
+use crate::util::config::{self, AuthHeader};
+use crate::util::error::{ApiError, ErrorKind};
+use crate::util::session;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+thread_local! {
+    static REQUEST_INTERCEPTORS: RefCell<Vec<Rc<dyn RequestInterceptor>>> = RefCell::new(vec![]);
+    static RESPONSE_INTERCEPTORS: RefCell<Vec<Rc<dyn ResponseInterceptor>>> = RefCell::new(vec![]);
+}
+
+/// What is about to be sent, interceptors add headers to it.
+/// For `user_cli` calls it describes the client configuration, url is the base path.
+#[derive(Clone, Debug)]
+pub struct RequestInfo {
+    pub url: String,
+    // login, register and the like: no credentials sent, no session redirects on the answer
+    pub anonymous: bool,
+    pub headers: Vec<(String, String)>,
+}
+
+impl RequestInfo {
+    pub fn new(url: &str, anonymous: bool) -> Self {
+        Self {
+            url: url.to_string(),
+            anonymous,
+            headers: vec![],
+        }
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// What came back, interceptors read it, turn it into an error or take it over.
+#[derive(Debug)]
+pub struct ResponseInfo {
+    pub method: String,
+    pub url: String,
+    pub anonymous: bool,
+    // None when there was no response, or the client does not tell
+    pub status: Option<u16>,
+    // body of a 4xx/5xx
+    pub body: Option<String>,
+    pub elapsed_millis: i64,
+    pub error: Option<ErrorKind>,
+    // an interceptor took over (redirected), the caller gets an error it should not show
+    pub handled: bool,
+}
+
+impl ResponseInfo {
+    pub fn new(method: &str, url: &str, anonymous: bool, started_millis: i64) -> Self {
+        Self {
+            method: method.to_string(),
+            url: url.to_string(),
+            anonymous,
+            status: None,
+            body: None,
+            elapsed_millis: now_millis() - started_millis,
+            error: None,
+            handled: false,
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self.status, Some(v) if v >= 400)
+    }
+}
+
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+pub trait RequestInterceptor {
+    fn intercept(&self, req: &mut RequestInfo);
+}
+
+pub trait ResponseInterceptor {
+    fn intercept(&self, res: &mut ResponseInfo);
+}
+
+pub fn add_request_interceptor<I>(interceptor: I)
+where
+    I: RequestInterceptor + 'static,
+{
+    REQUEST_INTERCEPTORS.with(|x| x.borrow_mut().push(Rc::new(interceptor)));
+}
+
+pub fn add_response_interceptor<I>(interceptor: I)
+where
+    I: ResponseInterceptor + 'static,
+{
+    RESPONSE_INTERCEPTORS.with(|x| x.borrow_mut().push(Rc::new(interceptor)));
+}
+
+/// run the request interceptors in the order they were added
+pub fn intercept_request(req: &mut RequestInfo) {
+    // cloned so an interceptor can send requests of its own
+    let interceptors = REQUEST_INTERCEPTORS.with(|x| x.borrow().clone());
+    for interceptor in interceptors {
+        interceptor.intercept(req);
+    }
+}
+
+/// run the response interceptors in the order they were added
+pub fn intercept_response(res: &mut ResponseInfo) {
+    let interceptors = RESPONSE_INTERCEPTORS.with(|x| x.borrow().clone());
+    for interceptor in interceptors {
+        interceptor.intercept(res);
+    }
+}
+
+/// the app's pipeline, called once at startup
+pub fn install() {
+    add_request_interceptor(Auth);
+    add_request_interceptor(RequestId);
+    add_request_interceptor(AcceptLanguage);
+    add_request_interceptor(ClientVersion);
+
+    add_response_interceptor(Timing);
+    add_response_interceptor(ErrorMapping);
+    add_response_interceptor(StatusRedirect);
+}
+
+/// the session token, in the header the backend is configured for; only to our own backend
+pub struct Auth;
+
+impl RequestInterceptor for Auth {
+    fn intercept(&self, req: &mut RequestInfo) {
+        if req.anonymous || !req.url.starts_with(&config::get().api_base()) {
+            return;
+        }
+        if let Ok(token) = common::get_token() {
+            let value = format!("Bearer {}", token);
+            match config::get().auth_header {
+                AuthHeader::Token => req.set_header("token", &value),
+                AuthHeader::Authorization => req.set_header("Authorization", &value),
+            }
+        }
+    }
+}
+
+/// a fresh id per request, to find it in the backend logs; for `user_cli` it is one per
+/// client configuration, which is why those are built per call
+pub struct RequestId;
+
+impl RequestInterceptor for RequestId {
+    fn intercept(&self, req: &mut RequestInfo) {
+        req.set_header("X-Request-Id", &uuid::Uuid::new_v4().to_string());
+    }
+}
+
+pub struct AcceptLanguage;
+
+impl RequestInterceptor for AcceptLanguage {
+    fn intercept(&self, req: &mut RequestInfo) {
+        if let Some(language) = web_sys::window().and_then(|x| x.navigator().language()) {
+            req.set_header("Accept-Language", &language);
+        }
+    }
+}
+
+pub struct ClientVersion;
+
+impl RequestInterceptor for ClientVersion {
+    fn intercept(&self, req: &mut RequestInfo) {
+        req.set_header("X-Client-Version", env!("CARGO_PKG_VERSION"));
+    }
+}
+
+pub struct Timing;
+
+impl ResponseInterceptor for Timing {
+    fn intercept(&self, res: &mut ResponseInfo) {
+        let status = res.status.map_or("-".to_string(), |x| x.to_string());
+        if res.is_failure() || res.error.is_some() {
+            log::warn!(
+                "{} {} {} {}ms",
+                res.method,
+                res.url,
+                status,
+                res.elapsed_millis
+            );
+        } else {
+            log::debug!(
+                "{} {} {} {}ms",
+                res.method,
+                res.url,
+                status,
+                res.elapsed_millis
+            );
+        }
+    }
+}
+
+/// 4xx/5xx bodies into `ErrorKind::ApiError`
+pub struct ErrorMapping;
+
+impl ResponseInterceptor for ErrorMapping {
+    fn intercept(&self, res: &mut ResponseInfo) {
+        if res.error.is_some() || !res.is_failure() {
+            return;
+        }
+        if let Some(status) = res.status {
+            let body = res.body.as_deref().unwrap_or_default();
+            res.error = Some(ErrorKind::ApiError(ApiError::from_body(status, body)));
+        }
+    }
+}
+
+/// leave the page on 401/404
+pub struct StatusRedirect;
+
+impl ResponseInterceptor for StatusRedirect {
+    fn intercept(&self, res: &mut ResponseInfo) {
+        if res.anonymous {
+            return;
+        }
+        match res.status {
+            Some(401) if common::get_token().is_ok() => {
+                // the token we sent is expired or revoked, log out instead of landing on /401
+                session::logout();
+                res.handled = true;
+            }
+            Some(401) => {
+                common::redirect_with_next("/401");
+                res.handled = true;
+            }
+            Some(404) => {
+                common::redirect("/404");
+                res.handled = true;
+            }
+            _ => (),
+        }
+    }
+}
@@ -1,3 +1,10 @@
 pub mod common;
+pub mod config;
+pub mod debounce;
 pub mod error;
-pub mod request;
\ No newline at end of file
+pub mod interceptor;
+pub mod permission;
+pub mod protocol;
+pub mod request;
+pub mod session;
+pub mod ws;
@@ -0,0 +1,51 @@
+use crate::util::common::{self, BasicResult};
+use crate::util::request;
+
+pub const ALL: &str = "*";
+pub const USER_READ: &str = "user:read";
+pub const ROLE_READ: &str = "role:read";
+
+/// permissions of the logged in user, stored together with `CurrentUser`
+pub async fn fetch() -> BasicResult<Vec<String>> {
+    let res = request::get::<Vec<String>, Vec<(&str, &str)>, _>(
+        request::Host::ApiBase,
+        "/user/permissions",
+        None,
+    )
+    .await?;
+    Ok(res.data.unwrap_or_default())
+}
+
+/// sessions stored before permissions were kept have none, load them once at startup
+/// instead of locking the user out of every guarded route until the next login
+pub async fn backfill() {
+    if common::get_token().is_err() {
+        return;
+    }
+    let mut user = match common::get_current_user() {
+        Ok(v) if v.permissions.is_empty() => v,
+        _ => return,
+    };
+    user.permissions = match fetch().await {
+        Ok(v) => v,
+        Err(err) => {
+            log::warn!("fetch permissions error: {}", err);
+            return;
+        }
+    };
+    match serde_json::to_string(&user) {
+        Ok(v) => common::set_session_item("current_user", &v),
+        Err(err) => log::error!("store current user error: {}", err),
+    }
+}
+
+pub fn has(permission: &str) -> bool {
+    match common::get_current_user() {
+        Ok(user) => user.permissions.iter().any(|x| x == ALL || x == permission),
+        Err(_) => false,
+    }
+}
+
+pub fn has_opt(permission: Option<&str>) -> bool {
+    permission.map_or(true, has)
+}
@@ -0,0 +1,222 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Bumped on incompatible changes, frames of another version are skipped.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Every frame goes over the wire as `{"v": 1, "type": "...", ...fields}`.
+/// Tagged and flattened frames are buffered by serde, which has no u128, ids are u64.
+#[derive(Serialize, Deserialize)]
+struct Envelope<T> {
+    v: u32,
+    #[serde(flatten)]
+    frame: T,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    v: Option<u32>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[cfg_attr(test, derive(Deserialize))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    Ping,
+    /// `client_id` comes back in the `Ack` or `Error` for this send
+    Send {
+        room: String,
+        content: String,
+        client_id: String,
+    },
+    /// to one session whatever rooms it is in, acked like `Send`
+    Direct {
+        to: String,
+        content: String,
+        client_id: String,
+    },
+    /// the server answers with a `JoinRoom` for us, or an `Error` when the name is taken
+    CreateRoom {
+        room: String,
+    },
+    JoinRoom {
+        room: String,
+    },
+    QuitRoom {
+        room: String,
+    },
+}
+
+impl ClientFrame {
+    pub fn send(room: &str, content: &str) -> Self {
+        ClientFrame::Send {
+            room: room.to_string(),
+            content: content.to_string(),
+            client_id: uuid::Uuid::new_v4().to_string(),
+        }
+    }
+
+    /// the id a send or direct message is acked under
+    pub fn client_id(&self) -> Option<&str> {
+        match self {
+            ClientFrame::Send { client_id, .. } | ClientFrame::Direct { client_id, .. } => {
+                Some(client_id)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerFrame {
+    Pong,
+    UpdateSession {
+        session_id: String,
+    },
+    /// room -> session id -> name
+    List {
+        rooms: HashMap<String, HashMap<String, String>>,
+    },
+    JoinRoom(RoomChange),
+    QuitRoom(RoomChange),
+    UpdateName {
+        session_id: String,
+        name: String,
+        // part of the frame, a rename only needs the new name
+        #[allow(dead_code)]
+        old_name: String,
+    },
+    Message(MessageContent),
+    /// a `Send` was stored, under this id and time
+    Ack {
+        client_id: String,
+        id: u64,
+        time: String,
+    },
+    /// `client_id` is set when a `Send` was refused
+    Error {
+        client_id: Option<String>,
+        msg: String,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct RoomChange {
+    pub session_id: String,
+    pub name: String,
+    pub room: String,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct MessageContent {
+    pub id: u64,
+    pub room: String,
+    pub from_id: String,
+    pub from_name: String,
+    pub content: String,
+    pub time: String,
+    pub is_own: Option<()>,
+    // the session a direct message is addressed to, None in a room
+    #[serde(default)]
+    pub to: Option<String>,
+    // set on our own sends, the server echoes it on the stored message
+    #[serde(default)]
+    pub client_id: Option<String>,
+    #[serde(skip)]
+    pub state: SendState,
+}
+
+/// Where one of our own messages is, never on the wire.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SendState {
+    #[default]
+    Sent,
+    Pending,
+    Failed(String),
+}
+
+pub fn encode(frame: &ClientFrame) -> Option<String> {
+    let envelope = Envelope {
+        v: PROTOCOL_VERSION,
+        frame,
+    };
+    match serde_json::to_string(&envelope) {
+        Ok(v) => Some(v),
+        Err(err) => {
+            log::error!("encode frame error: {}", err);
+            None
+        }
+    }
+}
+
+/// None for frames that are malformed, of an unknown type or another version, they are logged
+pub fn decode(text: &str) -> Option<ServerFrame> {
+    match serde_json::from_str::<Version>(text) {
+        Ok(Version {
+            v: Some(PROTOCOL_VERSION),
+        }) => (),
+        Ok(Version { v }) => {
+            log::warn!("skip frame of protocol version {:?}: {}", v, text);
+            return None;
+        }
+        Err(err) => {
+            log::warn!("skip malformed frame ({}): {}", err, text);
+            return None;
+        }
+    }
+    match serde_json::from_str::<Envelope<ServerFrame>>(text) {
+        Ok(v) => Some(v.frame),
+        Err(err) => {
+            log::warn!("skip unknown frame ({}): {}", err, text);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping_round_trip() {
+        let text = encode(&ClientFrame::Ping).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"v": PROTOCOL_VERSION, "type": "ping"})
+        );
+        let envelope: Envelope<ClientFrame> = serde_json::from_str(&text).unwrap();
+        assert_eq!(envelope.frame, ClientFrame::Ping);
+    }
+
+    #[test]
+    fn decode_frame() {
+        assert_eq!(decode(r#"{"v":1,"type":"pong"}"#), Some(ServerFrame::Pong));
+        assert_eq!(
+            decode(r#"{"v":1,"type":"ack","client_id":"c","id":7,"time":"t"}"#),
+            Some(ServerFrame::Ack {
+                client_id: "c".to_string(),
+                id: 7,
+                time: "t".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn skip_unknown_type() {
+        assert_eq!(decode(r#"{"v":1,"type":"shrug"}"#), None);
+    }
+
+    #[test]
+    fn skip_other_version() {
+        assert_eq!(decode(r#"{"v":2,"type":"pong"}"#), None);
+        assert_eq!(decode(r#"{"type":"pong"}"#), None);
+    }
+
+    #[test]
+    fn skip_invalid_json() {
+        assert_eq!(decode("message:{"), None);
+        assert_eq!(decode(r#"{"v":1,"type":"ack","client_id":"c"}"#), None);
+    }
+}
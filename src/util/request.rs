@@ -1,11 +1,24 @@
 use crate::util::common;
-use crate::util::error::ToError;
+use crate::util::config;
+use crate::util::error::{ApiError, ErrorKind};
+use crate::util::interceptor::{self, RequestInfo, ResponseInfo};
 use common::BasicResult;
+use gloo::timers::future::TimeoutFuture;
 use gloo_net::http::{Method, Request};
 use gloo_net::websocket::futures::WebSocket;
+use rand::Rng;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::future::Future;
 use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+
+thread_local! {
+    static RETRY_LISTENER: RefCell<Option<Callback<RetryNotice>>> = RefCell::new(None);
+    static IN_FLIGHT: RefCell<InFlight> = RefCell::new(InFlight::default());
+    static IN_FLIGHT_LISTENER: RefCell<Option<Callback<InFlight>>> = RefCell::new(None);
+}
 
 #[derive(Deserialize)]
 pub struct ResultData<T> {
@@ -15,6 +28,7 @@ pub struct ResultData<T> {
 }
 
 #[allow(unused)]
+#[derive(Clone, Copy)]
 pub enum Host {
     ApiBase,
     Base,
@@ -22,73 +36,358 @@ pub enum Host {
 
 impl std::fmt::Display for Host {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let base = config::get().api_base();
         match self {
-            Host::ApiBase => f.write_str("http://localhost:8881/api"),
-            Host::Base => f.write_str("http://localhost:8881"),
+            Host::ApiBase => f.write_fmt(format_args!("{}/api", base)),
+            Host::Base => f.write_str(&base),
         }
     }
 }
 
-fn build_header(req: Request) -> Request {
-    let req = req.header("Content-type", "application/json");
-    match common::get_token() {
-        Ok(v) => req.header("token", format!("Bearer {}", v).as_str()),
-        Err(_) => req,
+/// per call options, the default follows the configured retry policy
+#[derive(Clone, Copy, Debug)]
+pub struct RequestOptions {
+    pub retry: bool,
+}
+
+impl Default for RequestOptions {
+    fn default() -> Self {
+        Self { retry: true }
+    }
+}
+
+impl RequestOptions {
+    pub fn no_retry() -> Self {
+        Self { retry: false }
+    }
+}
+
+/// a failed attempt that is about to be retried
+#[derive(Clone, Debug)]
+pub struct RetryNotice {
+    pub path: String,
+    pub reason: String,
+    // the attempt coming next, starting at 2
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_millis: u32,
+}
+
+/// one listener (the layout) gets told about retries, None removes it
+pub fn set_retry_listener(listener: Option<Callback<RetryNotice>>) {
+    RETRY_LISTENER.with(|x| *x.borrow_mut() = listener);
+}
+
+pub fn is_retry_listener(listener: &Callback<RetryNotice>) -> bool {
+    RETRY_LISTENER.with(|x| x.borrow().as_ref() == Some(listener))
+}
+
+fn notify_retry(notice: RetryNotice) {
+    let listener = RETRY_LISTENER.with(|x| x.borrow().clone());
+    if let Some(listener) = listener {
+        listener.emit(notice);
+    }
+}
+
+/// requests on their way, provided to components as a yew context
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InFlight {
+    pub count: usize,
+    // keys of the tracked calls a component waits on, once per call
+    keys: Vec<&'static str>,
+}
+
+impl InFlight {
+    pub fn is_pending(&self, key: &str) -> bool {
+        self.keys.contains(&key)
+    }
+}
+
+pub fn in_flight() -> InFlight {
+    IN_FLIGHT.with(|x| x.borrow().clone())
+}
+
+/// one listener (the context provider) gets every change of the in-flight requests
+pub fn set_in_flight_listener(listener: Option<Callback<InFlight>>) {
+    IN_FLIGHT_LISTENER.with(|x| *x.borrow_mut() = listener);
+}
+
+fn update_in_flight<F>(f: F)
+where
+    F: FnOnce(&mut InFlight),
+{
+    let value = IN_FLIGHT.with(|x| {
+        let mut x = x.borrow_mut();
+        f(&mut x);
+        x.clone()
+    });
+    let listener = IN_FLIGHT_LISTENER.with(|x| x.borrow().clone());
+    if let Some(listener) = listener {
+        listener.emit(value);
+    }
+}
+
+// counted from creation until dropped, so a future dropped halfway is not left pending
+struct Pending(Option<&'static str>);
+
+impl Pending {
+    fn start(key: Option<&'static str>) -> Self {
+        update_in_flight(|x| {
+            x.count += 1;
+            x.keys.extend(key);
+        });
+        Self(key)
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        let key = self.0;
+        update_in_flight(|x| {
+            x.count = x.count.saturating_sub(1);
+            if let Some(index) = key.and_then(|key| x.keys.iter().position(|x| *x == key)) {
+                x.keys.remove(index);
+            }
+        });
+    }
+}
+
+/// count `fut` as in flight until it completes, for calls that do not go through this module
+pub async fn track<F>(fut: F) -> F::Output
+where
+    F: Future,
+{
+    let _pending = Pending::start(None);
+    fut.await
+}
+
+/// like `track`, and marks `key` pending so the component that sent it can tell
+pub async fn track_as<F>(key: &'static str, fut: F) -> F::Output
+where
+    F: Future,
+{
+    let _pending = Pending::start(Some(key));
+    fut.await
+}
+
+fn is_transient(err: &ErrorKind) -> bool {
+    matches!(err, ErrorKind::NetworkError(_)) || matches!(err.status(), Some(502 | 503 | 504))
+}
+
+/// a response an interceptor took over still fails, marked so callers do not show it
+fn handled_error(info: ResponseInfo) -> ErrorKind {
+    let mut err = match info.error {
+        Some(ErrorKind::ApiError(e)) => e,
+        _ => {
+            let status = info.status.unwrap_or_default();
+            ApiError {
+                status,
+                msg: format!("request failed with status {}", status),
+                ..Default::default()
+            }
+        }
+    };
+    err.redirected = true;
+    ErrorKind::ApiError(err)
+}
+
+/// exponential backoff with jitter over its upper half, so clients that failed together spread out
+fn backoff_millis(policy: &config::RetryPolicy, attempt: u32) -> u32 {
+    let delay = policy
+        .base_delay_millis
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(policy.max_delay_millis);
+    rand::thread_rng().gen_range(delay / 2..=delay)
+}
+
+fn method_name(method: &Method) -> &'static str {
+    match method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
     }
 }
 
 fn build_request(method: Method, host: Host, path: &str) -> Request {
-    let url = &format!("{}{}", host, path);
-    let req = Request::new(url).method(method);
-    build_header(req)
+    let url = format!("{}{}", host, path);
+    let mut info = RequestInfo::new(&url, false);
+    info.set_header("Content-type", "application/json");
+    interceptor::intercept_request(&mut info);
+    info.headers
+        .iter()
+        .fold(Request::new(&url).method(method), |req, (k, v)| {
+            req.header(k, v)
+        })
 }
 
-async fn send<Res>(req: gloo_net::http::Request) -> BasicResult<ResultData<Res>>
+async fn send<Res>(
+    method: &str,
+    host: Host,
+    path: &str,
+    req: gloo_net::http::Request,
+) -> BasicResult<ResultData<Res>>
 where
     Res: DeserializeOwned,
 {
-    let response = req.send().await?;
-    let status = response.status();
-    // let url = response.url();
-    if status == 401 || status == 404 {
-        common::redirect(&format!("/{status}"));
-
-        return Ok(ResultData {
-            data: None,
-            msg: None,
-            total: None,
-        });
+    let started = interceptor::now_millis();
+    let result = req.send().await;
+    let url = format!("{}{}", host, path);
+    let mut info = ResponseInfo::new(method, &url, false, started);
+    let response = match result {
+        Ok(response) => {
+            info.status = Some(response.status());
+            if info.is_failure() {
+                info.body = Some(response.text().await.unwrap_or_default());
+            }
+            Some(response)
+        }
+        Err(err) => {
+            info.error = Some(err.into());
+            None
+        }
+    };
+    interceptor::intercept_response(&mut info);
+    if info.handled {
+        return Err(handled_error(info));
     }
-    let status_first = status / 100;
-    let result: ResultData<Res> = response.json().await.map_err(|e| {
-        log::error!("json umarshal error: {}", e);
-        e
-    })?;
-    match status_first {
-        4 | 5 => match status {
-            452 => Err(result.msg.unwrap().to_hint()),
-            _ => Err(result.msg.unwrap().to_server_error()),
-        },
-        _ => Ok(result),
+    match (info.error, response) {
+        (Some(err), _) => Err(err),
+        (None, Some(response)) => {
+            let result: ResultData<Res> = response.json().await.map_err(|e| {
+                log::error!("json umarshal error: {}", e);
+                e
+            })?;
+            Ok(result)
+        }
+        (None, None) => Err(ErrorKind::OtherError(format!("no response from {}", url))),
+    }
+}
+
+async fn cli_with<T, E, F>(name: &str, anonymous: bool, fut: F) -> BasicResult<T>
+where
+    E: std::fmt::Debug,
+    F: Future<Output = Result<T, user_cli::apis::Error<E>>>,
+{
+    let started = interceptor::now_millis();
+    let result = track(fut).await;
+    let mut info = ResponseInfo::new("*", &format!("user_cli::{}", name), anonymous, started);
+    match result {
+        Ok(v) => {
+            interceptor::intercept_response(&mut info);
+            Ok(v)
+        }
+        Err(user_cli::apis::Error::ResponseError(res)) => {
+            info.status = Some(res.status.as_u16());
+            info.body = Some(res.content);
+            interceptor::intercept_response(&mut info);
+            if info.handled {
+                return Err(handled_error(info));
+            }
+            Err(info
+                .error
+                .unwrap_or(ErrorKind::OtherError("unmapped response error".to_string())))
+        }
+        Err(err) => {
+            info.error = Some(err.into());
+            interceptor::intercept_response(&mut info);
+            Err(info
+                .error
+                .unwrap_or(ErrorKind::OtherError("unmapped client error".to_string())))
+        }
+    }
+}
+
+/// await the `user_cli` call `name` made with `common::get_cli_config`, through the response interceptors
+pub async fn cli<T, E, F>(name: &str, fut: F) -> BasicResult<T>
+where
+    E: std::fmt::Debug,
+    F: Future<Output = Result<T, user_cli::apis::Error<E>>>,
+{
+    cli_with(name, false, fut).await
+}
+
+/// like `cli`, for calls made with `common::get_cli_config_without_token`
+pub async fn cli_anonymous<T, E, F>(name: &str, fut: F) -> BasicResult<T>
+where
+    E: std::fmt::Debug,
+    F: Future<Output = Result<T, user_cli::apis::Error<E>>>,
+{
+    cli_with(name, true, fut).await
+}
+
+/// send what `build` makes, again after a backoff while it fails transiently,
+/// only GET is retried since the other methods here are not safe to repeat
+async fn send_with_retry<Res, F>(
+    method: Method,
+    host: Host,
+    path: &str,
+    build: F,
+    options: RequestOptions,
+) -> BasicResult<ResultData<Res>>
+where
+    Res: DeserializeOwned,
+    F: Fn() -> Request,
+{
+    let policy = config::get().retry;
+    let max_attempts = if options.retry && matches!(method, Method::GET) {
+        policy.max_attempts.max(1)
+    } else {
+        1
+    };
+    let _pending = Pending::start(None);
+    let mut attempt = 1;
+    loop {
+        match send(method_name(&method), host, path, build()).await {
+            Err(err) if attempt < max_attempts && is_transient(&err) => {
+                let delay_millis = backoff_millis(&policy, attempt);
+                attempt += 1;
+                log::warn!(
+                    "{} failed: {}, retry {}/{} in {}ms",
+                    path,
+                    err,
+                    attempt,
+                    max_attempts,
+                    delay_millis
+                );
+                notify_retry(RetryNotice {
+                    path: path.to_string(),
+                    reason: err.to_string(),
+                    attempt,
+                    max_attempts,
+                    delay_millis,
+                });
+                TimeoutFuture::new(delay_millis).await;
+            }
+            res => return res,
+        }
     }
 }
 
 pub fn open_ws() -> BasicResult<WebSocket> {
-    let token = common::get_token().unwrap();
+    let token = common::get_token()?;
     spawn_local(async move {
-        get::<common::CurrentUser, Vec<(&str, &str)>, _>(
+        if let Err(err) = get::<common::CurrentUser, Vec<(&str, &str)>, _>(
             Host::ApiBase,
             "/user/get_current_user",
             None,
         )
         .await
-        .unwrap();
+        {
+            log::error!("get current user error: {}", err);
+        }
     });
 
-    let url = format!("ws://localhost:8881/ws/ws/{}", token);
-    let ws = WebSocket::open(&url)
-        .map_err(|err| log::info!("open ws error: {:#?}", err))
-        .unwrap();
+    let url = format!("{}/ws/ws/{}", config::get().ws_base(), token);
+    let ws = WebSocket::open(&url).map_err(|err| {
+        log::info!("open ws error: {:#?}", err);
+        ErrorKind::NetworkError(err.to_string())
+    })?;
     Ok(ws)
 }
 
@@ -102,12 +401,34 @@ where
     Res: DeserializeOwned,
     V: AsRef<str>,
 {
-    let req = match params {
-        Some(p) => build_request(Method::GET, host, path).query(p),
+    get_with(host, path, params, RequestOptions::default()).await
+}
+
+pub async fn get_with<'a, Res, Param, V>(
+    host: Host,
+    path: &str,
+    params: Option<Param>,
+    options: RequestOptions,
+) -> BasicResult<ResultData<Res>>
+where
+    Param: IntoIterator<Item = (&'a str, V)>,
+    Res: DeserializeOwned,
+    V: AsRef<str>,
+{
+    // every attempt builds its own request
+    let params: Option<Vec<(&str, String)>> = params.map(|p| {
+        p.into_iter()
+            .map(|(k, v)| (k, v.as_ref().to_string()))
+            .collect()
+    });
+    let build = || match &params {
+        Some(p) => {
+            build_request(Method::GET, host, path).query(p.iter().map(|(k, v)| (*k, v.as_str())))
+        }
         None => build_request(Method::GET, host, path),
     };
 
-    send(req).await
+    send_with_retry(Method::GET, host, path, build, options).await
 }
 
 #[allow(unused)]
@@ -118,7 +439,7 @@ where
 {
     let body = serde_json::to_string(body)?;
     let req = build_request(Method::PUT, host, path).body(body);
-    send(req).await
+    track(send("PUT", host, path, req)).await
 }
 
 #[allow(unused)]
@@ -133,7 +454,7 @@ where
 {
     let body = serde_json::to_string(body)?;
     let req = build_request(Method::POST, host, path).body(body);
-    send(req).await
+    track(send("POST", host, path, req)).await
 }
 
 #[allow(unused)]
@@ -148,5 +469,5 @@ where
 {
     let body = serde_json::to_string(body)?;
     let req = build_request(Method::DELETE, host, path).body(body);
-    send(req).await
+    track(send("DELETE", host, path, req)).await
 }
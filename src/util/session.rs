@@ -0,0 +1,137 @@
+use crate::util::common::{self, BasicResult};
+use crate::util::config;
+use crate::util::request;
+use crate::util::ws;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use gloo::timers::callback::Timeout;
+use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+
+// refresh this long before expire_at when the backend supports it
+const REFRESH_AHEAD_MILLIS: i64 = 60 * 1000;
+// otherwise warn this long before expire_at
+const WARN_AHEAD_MILLIS: i64 = 5 * 60 * 1000;
+// setTimeout overflows above i32::MAX
+const MAX_DELAY_MILLIS: i64 = i32::MAX as i64;
+
+pub enum SessionEvent {
+    /// minutes left until the session expires
+    Expiring(i64),
+    Expired,
+    Refreshed,
+    RefreshFailed(String),
+    /// nothing was due within one timer's reach, schedule again
+    Reschedule,
+}
+
+/// expire_at is either rfc3339 or a local `yyyy-mm-dd HH:MM:SS`
+fn parse_expire_at(expire_at: &str) -> Option<i64> {
+    if let Ok(v) = chrono::DateTime::parse_from_rfc3339(expire_at) {
+        return Some(v.timestamp_millis());
+    }
+    NaiveDateTime::parse_from_str(expire_at, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|x| Local.from_local_datetime(&x).single())
+        .map(|x| x.timestamp_millis())
+}
+
+/// milliseconds until the stored session expires, None when there is no session
+/// or its expire_at can not be read
+pub fn millis_left() -> Option<i64> {
+    let user = common::get_current_user().ok()?;
+    let expire_at = parse_expire_at(&user.expire_at).or_else(|| {
+        log::warn!("unknown expire_at format: {}", user.expire_at);
+        None
+    })?;
+    Some(expire_at - Utc::now().timestamp_millis())
+}
+
+pub fn minutes_left() -> i64 {
+    millis_left().map_or(0, |x| (x.max(0) + 59_999) / 60_000)
+}
+
+/// drop the session and go back to login
+pub fn logout() {
+    ws::disconnect();
+    common::delete_current_user().unwrap_or_else(|x| {
+        log::error!("{:?}", x);
+    });
+    common::redirect("/login");
+}
+
+/// exchange the current token for a new one and reload the current user with its new expire_at
+pub async fn refresh() -> BasicResult<()> {
+    let res =
+        request::post::<String, _>(request::Host::ApiBase, "/user/refresh_token", &()).await?;
+    if let Some(token) = res.data {
+        common::set_token(&token);
+    }
+    common::refresh_current_user().await?;
+    Ok(())
+}
+
+fn timeout<F>(delay_millis: i64, f: F) -> Timeout
+where
+    F: 'static + FnOnce(),
+{
+    Timeout::new(delay_millis.clamp(0, MAX_DELAY_MILLIS) as u32, f)
+}
+
+/// Schedules the refresh or the expiring warning, and the logout, from `CurrentUser.expire_at`.
+/// Dropping it cancels everything scheduled.
+#[derive(Default)]
+pub struct SessionManager {
+    pending: Vec<Timeout>,
+}
+
+impl SessionManager {
+    /// (re)schedule from the stored current user, replacing what was scheduled before
+    pub fn schedule(&mut self, on_event: Callback<SessionEvent>) {
+        self.cancel();
+        let left = match millis_left() {
+            Some(v) => v,
+            None => return,
+        };
+
+        if left <= 0 {
+            self.pending
+                .push(timeout(0, move || on_event.emit(SessionEvent::Expired)));
+            return;
+        }
+
+        // a longer timer would fire at the cap, long before anything is due
+        if left - WARN_AHEAD_MILLIS.max(REFRESH_AHEAD_MILLIS) > MAX_DELAY_MILLIS {
+            self.pending.push(timeout(MAX_DELAY_MILLIS, move || {
+                on_event.emit(SessionEvent::Reschedule)
+            }));
+            return;
+        }
+
+        if config::get().features.session_refresh {
+            let on_event = on_event.clone();
+            self.pending
+                .push(timeout(left - REFRESH_AHEAD_MILLIS, move || {
+                    spawn_local(async move {
+                        match refresh().await {
+                            Ok(_) => on_event.emit(SessionEvent::Refreshed),
+                            Err(err) => on_event.emit(SessionEvent::RefreshFailed(err.to_string())),
+                        }
+                    })
+                }));
+        } else {
+            let on_event = on_event.clone();
+            self.pending
+                .push(timeout(left - WARN_AHEAD_MILLIS, move || {
+                    on_event.emit(SessionEvent::Expiring(minutes_left()))
+                }));
+        }
+
+        self.pending
+            .push(timeout(left, move || on_event.emit(SessionEvent::Expired)));
+    }
+
+    pub fn cancel(&mut self) {
+        // dropping a Timeout clears it
+        self.pending.clear();
+    }
+}
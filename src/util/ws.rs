@@ -0,0 +1,398 @@
+use crate::util::common;
+use crate::util::protocol::{self, ClientFrame, ServerFrame};
+use crate::util::request;
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::{SinkExt, StreamExt};
+use gloo::events::EventListener;
+use gloo::timers::callback::Timeout;
+use gloo_net::websocket::Message;
+use rand::Rng;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+
+// the backend answers a ping frame with a pong frame
+const PING_INTERVAL_MILLIS: u32 = 20_000;
+// nothing heard for this long (pongs included) means the socket is half-open
+const SILENCE_TIMEOUT_MILLIS: i64 = 50_000;
+const RECONNECT_BASE_MILLIS: u32 = 1_000;
+const RECONNECT_MAX_MILLIS: u32 = 30_000;
+// sends kept while offline, the oldest go first
+const MAX_QUEUE: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WsStatus {
+    Online,
+    Reconnecting,
+    Offline,
+}
+
+impl WsStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WsStatus::Online => "online",
+            WsStatus::Reconnecting => "reconnecting",
+            WsStatus::Offline => "offline",
+        }
+    }
+}
+
+struct Inner {
+    started: bool,
+    status: WsStatus,
+    // bumped on every connection attempt, tasks and timers of older ones stand down
+    generation: u64,
+    attempt: u32,
+    outgoing: Option<UnboundedSender<Message>>,
+    queue: VecDeque<ClientFrame>,
+    last_seen: i64,
+    next_listener_id: u64,
+    frame_listeners: Vec<(u64, Callback<ServerFrame>)>,
+    status_listeners: Vec<(u64, Callback<WsStatus>)>,
+    network_listeners: Vec<EventListener>,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            started: false,
+            status: WsStatus::Offline,
+            generation: 0,
+            attempt: 0,
+            outgoing: None,
+            queue: Default::default(),
+            last_seen: 0,
+            next_listener_id: 0,
+            frame_listeners: vec![],
+            status_listeners: vec![],
+            network_listeners: vec![],
+        }
+    }
+}
+
+thread_local! {
+    static INNER: RefCell<Inner> = RefCell::new(Inner::default());
+}
+
+// listeners are never called from in here, they may call back into this module
+fn with<R, F>(f: F) -> R
+where
+    F: FnOnce(&mut Inner) -> R,
+{
+    INNER.with(|x| f(&mut x.borrow_mut()))
+}
+
+fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn is_current(generation: u64) -> bool {
+    with(|x| x.started && x.generation == generation)
+}
+
+fn is_browser_online() -> bool {
+    web_sys::window().map_or(true, |x| x.navigator().on_line())
+}
+
+fn set_status(status: WsStatus) {
+    let listeners = with(|x| {
+        if x.status == status {
+            return vec![];
+        }
+        x.status = status;
+        x.status_listeners
+            .iter()
+            .map(|(_, v)| v.clone())
+            .collect::<Vec<_>>()
+    });
+    for listener in listeners {
+        listener.emit(status);
+    }
+}
+
+/// Unsubscribes when dropped.
+pub struct Subscription(u64);
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let id = self.0;
+        with(|x| {
+            x.frame_listeners.retain(|(k, _)| *k != id);
+            x.status_listeners.retain(|(k, _)| *k != id);
+        });
+    }
+}
+
+/// every well-formed frame from the server, heartbeat replies excluded
+pub fn subscribe(on_frame: Callback<ServerFrame>) -> Subscription {
+    with(|x| {
+        x.next_listener_id += 1;
+        x.frame_listeners.push((x.next_listener_id, on_frame));
+        Subscription(x.next_listener_id)
+    })
+}
+
+pub fn subscribe_status(on_status: Callback<WsStatus>) -> Subscription {
+    with(|x| {
+        x.next_listener_id += 1;
+        x.status_listeners.push((x.next_listener_id, on_status));
+        Subscription(x.next_listener_id)
+    })
+}
+
+pub fn status() -> WsStatus {
+    with(|x| x.status)
+}
+
+/// Start the shared connection, it then reconnects by itself until `disconnect`.
+/// Returns false when it was already started.
+pub fn connect() -> bool {
+    if with(|x| std::mem::replace(&mut x.started, true)) {
+        return false;
+    }
+    watch_network();
+    open();
+    true
+}
+
+/// close for good (logout), drop what is queued
+pub fn disconnect() {
+    with(|x| {
+        x.started = false;
+        x.generation += 1;
+        x.attempt = 0;
+        x.outgoing = None;
+        x.queue.clear();
+        x.network_listeners.clear();
+    });
+    set_status(WsStatus::Offline);
+}
+
+/// Send now when online, otherwise queue until the next connection is up.
+/// True when the frame went to the socket, false when it was queued (or could not be encoded);
+/// queued frames go out before the status turns `Online` again.
+pub fn send(frame: ClientFrame) -> bool {
+    let text = match protocol::encode(&frame) {
+        Some(v) => v,
+        None => return false,
+    };
+    with(|x| {
+        if let Some(tx) = x.outgoing.as_ref().filter(|_| x.status == WsStatus::Online) {
+            match tx.unbounded_send(Message::Text(text)) {
+                Ok(_) => return true,
+                Err(err) => log::debug!("ws send error, queueing: {}", err),
+            }
+        }
+        if x.queue.len() >= MAX_QUEUE {
+            log::warn!("ws queue is full, dropping the oldest message");
+            x.queue.pop_front();
+        }
+        x.queue.push_back(frame);
+        false
+    })
+}
+
+/// whether the send or direct message `client_id` is waiting in the offline queue
+pub fn is_queued(client_id: &str) -> bool {
+    with(|x| x.queue.iter().any(|v| v.client_id() == Some(client_id)))
+}
+
+fn watch_network() {
+    let window = match web_sys::window() {
+        Some(v) => v,
+        None => return,
+    };
+    let online = EventListener::new(&window, "online", |_| {
+        if with(|x| x.started && x.status != WsStatus::Online) {
+            with(|x| x.attempt = 0);
+            open();
+        }
+    });
+    let offline = EventListener::new(&window, "offline", |_| {
+        if with(|x| x.started) {
+            set_status(WsStatus::Offline);
+        }
+    });
+    with(|x| x.network_listeners = vec![online, offline]);
+}
+
+fn open() {
+    let generation = with(|x| {
+        x.generation += 1;
+        x.outgoing = None;
+        x.generation
+    });
+    if common::get_token().is_err() {
+        // logged out, nothing to reconnect with; may run inside the online listener,
+        // so the listeners are left to the next connect
+        with(|x| x.started = false);
+        set_status(WsStatus::Offline);
+        return;
+    }
+    if !is_browser_online() {
+        set_status(WsStatus::Offline);
+        return;
+    }
+    set_status(WsStatus::Reconnecting);
+    let ws = match request::open_ws() {
+        Ok(v) => v,
+        Err(err) => {
+            log::warn!("open ws error: {}", err);
+            schedule_reconnect(generation);
+            return;
+        }
+    };
+
+    let (mut writer, mut reader) = ws.split();
+    let (tx, mut rx) = mpsc::unbounded::<Message>();
+    with(|x| {
+        x.outgoing = Some(tx);
+        x.last_seen = now_millis();
+    });
+
+    spawn_local(async move {
+        // ready once the socket is open
+        if futures::future::poll_fn(|cx| writer.poll_ready_unpin(cx))
+            .await
+            .is_err()
+        {
+            return;
+        }
+        on_open(generation);
+        // ends when the sender is dropped: reconnect, half-open or disconnect
+        while let Some(msg) = rx.next().await {
+            if let Err(err) = writer.send(msg).await {
+                log::warn!("ws send error: {:?}", err);
+                break;
+            }
+        }
+        if let Err(err) = writer.close().await {
+            log::debug!("ws close error: {:?}", err);
+        }
+    });
+
+    spawn_local(async move {
+        while let Some(msg) = reader.next().await {
+            match msg {
+                Ok(Message::Text(text)) => on_frame(generation, text),
+                Ok(Message::Bytes(_)) => (),
+                Err(err) => {
+                    log::info!("ws closed: {:?}", err);
+                    break;
+                }
+            }
+        }
+        on_closed(generation);
+    });
+}
+
+fn on_open(generation: u64) {
+    if !is_current(generation) {
+        return;
+    }
+    with(|x| {
+        x.attempt = 0;
+        x.last_seen = now_millis();
+        for frame in std::mem::take(&mut x.queue) {
+            let text = match protocol::encode(&frame) {
+                Some(v) => v,
+                None => continue,
+            };
+            if let Some(tx) = &x.outgoing {
+                if let Err(err) = tx.unbounded_send(Message::Text(text)) {
+                    log::debug!("ws flush error: {}", err);
+                }
+            }
+        }
+    });
+    set_status(WsStatus::Online);
+    heartbeat(generation);
+}
+
+fn on_frame(generation: u64, text: String) {
+    if !is_current(generation) {
+        return;
+    }
+    // any frame proves the socket alive, malformed ones included
+    with(|x| x.last_seen = now_millis());
+    let frame = match protocol::decode(&text) {
+        Some(ServerFrame::Pong) | None => return,
+        Some(v) => v,
+    };
+    let listeners = with(|x| {
+        x.frame_listeners
+            .iter()
+            .map(|(_, v)| v.clone())
+            .collect::<Vec<_>>()
+    });
+    for listener in listeners {
+        listener.emit(frame.clone());
+    }
+}
+
+fn on_closed(generation: u64) {
+    if !is_current(generation) {
+        return;
+    }
+    // the reader ending after a half-open reconnect was scheduled reports the same connection
+    if with(|x| x.outgoing.take().is_some()) {
+        schedule_reconnect(generation);
+    }
+}
+
+fn heartbeat(generation: u64) {
+    // a forgotten timeout per tick, the generation check retires it
+    Timeout::new(PING_INTERVAL_MILLIS, move || {
+        if !is_current(generation) {
+            return;
+        }
+        let silent = now_millis() - with(|x| x.last_seen);
+        if silent > SILENCE_TIMEOUT_MILLIS {
+            log::warn!("ws silent for {}ms, reconnecting", silent);
+            on_closed(generation);
+            return;
+        }
+        let ping = match protocol::encode(&ClientFrame::Ping) {
+            Some(v) => v,
+            None => return,
+        };
+        with(|x| {
+            if let Some(tx) = &x.outgoing {
+                if let Err(err) = tx.unbounded_send(Message::Text(ping)) {
+                    log::debug!("ws ping error: {}", err);
+                }
+            }
+        });
+        heartbeat(generation);
+    })
+    .forget();
+}
+
+fn backoff_millis(attempt: u32) -> u32 {
+    let delay = RECONNECT_BASE_MILLIS
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RECONNECT_MAX_MILLIS);
+    rand::thread_rng().gen_range(delay / 2..=delay)
+}
+
+fn schedule_reconnect(generation: u64) {
+    if !is_browser_online() {
+        // the online event reconnects
+        set_status(WsStatus::Offline);
+        return;
+    }
+    let attempt = with(|x| {
+        x.attempt += 1;
+        x.attempt
+    });
+    let delay = backoff_millis(attempt);
+    log::info!("ws reconnect {} in {}ms", attempt, delay);
+    set_status(WsStatus::Reconnecting);
+    Timeout::new(delay, move || {
+        if is_current(generation) {
+            open();
+        }
+    })
+    .forget();
+}